@@ -0,0 +1,146 @@
+use std::time::Instant;
+
+use crate::dictionary::Dictionary;
+use crate::find_and_sum;
+use crate::scanner::{self, Scanner};
+
+const FILLER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const WORDS: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+
+/// Builds a calibration document of `lines` lines mixing filler letters, digits and spelled
+/// numbers. The seed is fixed, so timings from separate runs are always measured on the same text.
+fn generate(lines: usize) -> String {
+    let mut state: u64 = 2023;
+    let mut next = move |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) % bound as u64) as usize
+    };
+
+    let mut document = String::new();
+    for _ in 0..lines {
+        let length = 20 + next(60);
+        let mut line = String::new();
+
+        while line.len() < length {
+            match next(20) {
+                0 => line.push((b'0' + next(10) as u8) as char),
+                1 => line.push_str(WORDS[next(10)]),
+                _ => line.push(FILLER[next(FILLER.len())] as char),
+            }
+        }
+
+        document.push_str(&line);
+        document.push('\n');
+    }
+
+    document
+}
+
+/// Day one's original `find_and_sum`, from before the `Dictionary`, kept as the baseline the other
+/// two are measured against. Only the panic on a line without digits is now a `None`.
+fn original_find_and_sum(line: &str) -> Option<u32> {
+    let mut left_idx = 0;
+    let mut right_idx = line.len().saturating_sub(1);
+
+    let mut left_number = None;
+    let mut right_number = None;
+
+    while left_idx <= right_idx {
+        left_number = line.chars().nth(left_idx).and_then(|c| c.to_digit(10));
+        right_number = line.chars().nth(right_idx).and_then(|c| c.to_digit(10));
+
+        if left_number.is_none() {
+            left_idx += 1;
+        }
+
+        if right_number.is_none() {
+            right_idx = right_idx.saturating_sub(1);
+        }
+
+        if left_number.is_some() && right_number.is_some() {
+            break;
+        }
+    }
+
+    if let (Some(left), Some(right)) = (left_number, right_number) {
+        Some(format!("{}{}", left, right).parse::<u32>().unwrap())
+    } else {
+        None
+    }
+}
+
+/// The original part two: spell the words out with their digit in the middle, then search as in
+/// part one.
+fn original_part_two(line: &str) -> Option<u32> {
+    let replacements = [
+        ("zero", "z0o"),
+        ("one", "o1e"),
+        ("two", "t2w"),
+        ("three", "t3e"),
+        ("four", "f4r"),
+        ("five", "f5e"),
+        ("six", "s6x"),
+        ("seven", "s7n"),
+        ("eight", "e8t"),
+        ("nine", "n9e"),
+    ];
+
+    let line = replacements.iter().fold(line.to_string(), |acc, (from, to)| {
+        acc.replace(from, to)
+    });
+    original_find_and_sum(&line)
+}
+
+fn throughput(bytes: usize, seconds: f64) -> String {
+    format!("{:.1} MB/s", bytes as f64 / seconds / 1_000_000.0)
+}
+
+/// `day1 bench [--lines N]` times the original search, the `Dictionary` scan and the streaming byte
+/// scanner on a generated document, and checks that all three agree.
+pub fn run(args: &[String]) {
+    let mut lines = 1_000_000;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lines" => lines = args.next().and_then(|n| n.parse().ok()).expect("--lines needs a number"),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let document = generate(lines);
+    println!("Document: {} lines, {} bytes, digit search: {}", lines, document.len(), scanner::digits::IMPLEMENTATION);
+
+    let parts = [
+        ("part one", Dictionary::digits_only(), original_find_and_sum as fn(&str) -> Option<u32>),
+        ("part two", Dictionary::english(), original_part_two),
+    ];
+
+    for (name, dictionary, original) in parts {
+        let started = Instant::now();
+        let baseline = document.lines().filter_map(original).map(u64::from).sum::<u64>();
+        let original_time = started.elapsed().as_secs_f64();
+
+        let started = Instant::now();
+        let expected = document.lines().filter_map(|line| find_and_sum(line, &dictionary)).sum::<u64>();
+        let dictionary_time = started.elapsed().as_secs_f64();
+
+        let started = Instant::now();
+        let totals = scanner::sum_reader(document.as_bytes(), &[Scanner::new(&dictionary)]).unwrap()[0];
+        let scanner_time = started.elapsed().as_secs_f64();
+
+        assert_eq!(baseline, expected, "Original and dictionary disagree on {}", name);
+        assert_eq!(expected, totals.sum, "Scanner and dictionary disagree on {}", name);
+
+        println!("{}: sum {}", name, totals.sum);
+        for (implementation, seconds) in [("original", original_time), ("dictionary", dictionary_time), ("scanner", scanner_time)] {
+            println!(
+                "  {:<10} {:.3}s ({}), {:.1}x the original",
+                implementation,
+                seconds,
+                throughput(document.len(), seconds),
+                original_time / seconds
+            );
+        }
+    }
+}
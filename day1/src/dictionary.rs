@@ -0,0 +1,273 @@
+/// The zero of every run of ten Unicode decimal digits (general category Nd, Unicode 14), in
+/// ascending order. Each zero is followed by the digits one to nine.
+const DECIMAL_ZEROS: [u32; 66] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66,
+    0x0CE6, 0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946,
+    0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0,
+    0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0, 0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0,
+    0x112F0, 0x11450, 0x114D0, 0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50,
+    0x11DA0, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140,
+    0x1E2F0, 0x1E950, 0x1FBF0,
+];
+
+/// The value of any Unicode decimal digit, such as full-width `３` or Arabic-Indic `٣`.
+fn unicode_digit(c: char) -> Option<u32> {
+    let code = c as u32;
+    let zero = match DECIMAL_ZEROS.binary_search(&code) {
+        Ok(index) => DECIMAL_ZEROS[index],
+        Err(0) => return None,
+        Err(index) => DECIMAL_ZEROS[index - 1],
+    };
+
+    (code - zero < 10).then_some(code - zero)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Digit,
+    /// The dictionary word that matched, as written in the dictionary.
+    Word(String),
+}
+
+/// A number found in a line, either a literal digit or a spelled-out word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub value: u64,
+    /// Byte offset of the match within the line.
+    pub start: usize,
+    /// Length of the match in bytes.
+    pub len: usize,
+    pub source: Source,
+}
+
+/// The largest value a dictionary word may stand for. Joining two values of at most nine digits
+/// side by side always fits in a `u64`.
+pub const MAX_VALUE: u64 = 999_999_999;
+
+/// Words that stand for numbers, such as `"eight" => 8` or `"eleven" => 11`. Literal digits always
+/// count as well, so an empty dictionary gives the part one behaviour. Lines are scanned by
+/// character, so multi-byte UTF-8 text is handled throughout.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    entries: Vec<(String, u64)>,
+    ignore_case: bool,
+    /// Whether digits from any script count, rather than only ASCII `0` to `9`.
+    unicode_digits: bool,
+}
+
+impl Dictionary {
+    pub fn digits_only() -> Dictionary {
+        Dictionary::default()
+    }
+
+    pub fn english() -> Dictionary {
+        let words = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+
+        Dictionary {
+            entries: words.iter().enumerate().map(|(value, word)| (word.to_string(), value as u64)).collect(),
+            ..Dictionary::default()
+        }
+    }
+
+    /// Reads a dictionary with one `word value` pair per line. Blank lines and lines starting with
+    /// `#` are skipped.
+    pub fn parse(text: &str) -> Result<Dictionary, String> {
+        let mut entries = Vec::new();
+
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (word, value) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| format!("Expected `word value`, got {}", line))?;
+            let value = value.parse::<u64>().map_err(|_| format!("Invalid value in {}", line))?;
+            if value > MAX_VALUE {
+                return Err(format!("Value out of range in {}, the largest allowed is {}", line, MAX_VALUE));
+            }
+            entries.push((word.trim().to_string(), value));
+        }
+
+        Ok(Dictionary { entries, ..Dictionary::default() })
+    }
+
+    pub fn ignore_case(mut self, ignore_case: bool) -> Dictionary {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn unicode_digits(mut self, unicode_digits: bool) -> Dictionary {
+        self.unicode_digits = unicode_digits;
+        self
+    }
+
+    /// Whether lines with non-ASCII text need the full character-level scan: when non-ASCII
+    /// digits count, or when case folding may map a non-ASCII letter onto a word.
+    pub fn needs_unicode(&self) -> bool {
+        self.unicode_digits || self.ignore_case
+    }
+
+    pub fn entries(&self) -> &[(String, u64)] {
+        &self.entries
+    }
+
+    pub fn ignores_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    fn chars_match(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    /// The number starting at byte `start` of `line`: a digit, or else the longest word that matches.
+    fn number_at(&self, line: &str, start: usize) -> Option<Match> {
+        let rest = &line[start..];
+        let first = rest.chars().next()?;
+
+        let digit = if self.unicode_digits { unicode_digit(first) } else { first.to_digit(10) };
+        if let Some(value) = digit {
+            return Some(Match { value: value as u64, start, len: first.len_utf8(), source: Source::Digit });
+        }
+
+        let mut best: Option<Match> = None;
+        for (word, value) in self.entries.iter() {
+            let mut len = 0;
+            let mut chars = rest.chars();
+            let matched = word.chars().all(|expected| {
+                chars.next().is_some_and(|c| {
+                    len += c.len_utf8();
+                    self.chars_match(c, expected)
+                })
+            });
+
+            if matched && !word.is_empty() && best.as_ref().is_none_or(|best| len > best.len) {
+                best = Some(Match { value: *value, start, len, source: Source::Word(word.to_string()) });
+            }
+        }
+
+        best
+    }
+
+    /// Scans forwards for the first number in the line. Words are matched where they start, so
+    /// overlapping words such as `eightwo` need no special handling.
+    pub fn first(&self, line: &str) -> Option<Match> {
+        line.char_indices().find_map(|(start, _)| self.number_at(line, start))
+    }
+
+    /// Scans backwards for the number that starts last in the line.
+    pub fn last(&self, line: &str) -> Option<Match> {
+        line.char_indices().rev().find_map(|(start, _)| self.number_at(line, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(dictionary: &Dictionary, line: &str) -> Option<(u64, u64)> {
+        Some((dictionary.first(line)?.value, dictionary.last(line)?.value))
+    }
+
+    #[test]
+    fn matches_overlapping_words() {
+        let dictionary = Dictionary::english();
+
+        assert_eq!(values(&dictionary, "eightwo"), Some((8, 2)));
+        assert_eq!(values(&dictionary, "xtwone3four"), Some((2, 4)));
+        assert_eq!(values(&dictionary, "oneight"), Some((1, 8)));
+        assert_eq!(values(&dictionary, "7pqrstsixteen"), Some((7, 6)));
+    }
+
+    #[test]
+    fn prefers_the_longest_word() {
+        let dictionary = Dictionary::parse("seven 7\nseventeen 17").unwrap();
+
+        assert_eq!(values(&dictionary, "seventeen"), Some((17, 17)));
+        assert_eq!(values(&dictionary, "sevent"), Some((7, 7)));
+    }
+
+    #[test]
+    fn ignores_case_only_when_asked() {
+        let dictionary = Dictionary::english();
+
+        assert_eq!(values(&dictionary, "EightWO"), None);
+        assert_eq!(values(&dictionary.clone().ignore_case(true), "EightWO"), Some((8, 2)));
+        assert_eq!(values(&dictionary.ignore_case(true), "xONE2"), Some((1, 2)));
+    }
+
+    #[test]
+    fn parses_dictionaries() {
+        let dictionary = Dictionary::parse("# Numbers past nine\n\nten 10\n  eleven   11  \ntwenty one 21\n").unwrap();
+
+        assert_eq!(dictionary.entries, [
+            ("ten".to_string(), 10),
+            ("eleven".to_string(), 11),
+            ("twenty one".to_string(), 21),
+        ]);
+    }
+
+    #[test]
+    fn rejects_malformed_dictionaries() {
+        assert_eq!(Dictionary::parse("ten").unwrap_err(), "Expected `word value`, got ten");
+        assert_eq!(Dictionary::parse("ten x").unwrap_err(), "Invalid value in ten x");
+        assert_eq!(Dictionary::parse("ten 10\nminus -1").unwrap_err(), "Invalid value in minus -1");
+    }
+
+    #[test]
+    fn rejects_values_too_large_to_join() {
+        assert!(Dictionary::parse("big 999999999").is_ok());
+        assert_eq!(
+            Dictionary::parse("huge 1000000000").unwrap_err(),
+            "Value out of range in huge 1000000000, the largest allowed is 999999999"
+        );
+    }
+
+    #[test]
+    fn recognises_digits_from_other_scripts() {
+        let dictionary = Dictionary::digits_only().unicode_digits(true);
+
+        assert_eq!(values(&dictionary, "１abc２"), Some((1, 2)));
+        assert_eq!(values(&dictionary, "٣x٧"), Some((3, 7)));
+        assert_eq!(values(&dictionary, "۴ and ९"), Some((4, 9)));
+        assert_eq!(values(&dictionary, "𝟘 then 5"), Some((0, 5)));
+    }
+
+    #[test]
+    fn ignores_other_scripts_by_default() {
+        let dictionary = Dictionary::digits_only();
+
+        assert_eq!(values(&dictionary, "１a3b٧"), Some((3, 3)));
+        assert_eq!(values(&dictionary, "１２３"), None);
+    }
+
+    #[test]
+    fn rejects_characters_next_to_digit_runs() {
+        for c in ['/', ':', '٪', '\u{FF1A}', 'x', '²', 'Ⅷ'] {
+            assert_eq!(unicode_digit(c), None, "{:?}", c);
+        }
+        assert_eq!(unicode_digit('\u{FF19}'), Some(9));
+        assert_eq!(unicode_digit('\u{1FBF9}'), Some(9));
+    }
+
+    #[test]
+    fn matches_words_and_digits_in_mixed_scripts() {
+        let dictionary = Dictionary::parse("eins 1\nzwei 2\nfünf 5\nдва 2").unwrap().unicode_digits(true);
+
+        assert_eq!(values(&dictionary, "ß fünf ٣ zwei"), Some((5, 2)));
+        assert_eq!(values(&dictionary, "число два и ８"), Some((2, 8)));
+        assert_eq!(values(&dictionary, "FÜNF und ДВА"), None);
+        assert_eq!(values(&dictionary.ignore_case(true), "FÜNF und ДВА"), Some((5, 2)));
+    }
+
+    #[test]
+    fn reports_byte_offsets_of_multi_byte_matches() {
+        let dictionary = Dictionary::parse("fünf 5").unwrap().unicode_digits(true);
+
+        let first = dictionary.first("éé fünf").unwrap();
+        assert_eq!((first.start, first.len), (5, 5));
+
+        let last = dictionary.last("fünf x ٧").unwrap();
+        assert_eq!((last.start, last.len), (8, 2));
+    }
+}
@@ -1,69 +1,234 @@
+mod bench;
+mod dictionary;
+mod scanner;
 
-fn part_one(input: &str) { 
-    let sum = input.lines().map(|input| find_and_sum(input)).sum::<u32>();
-    println!("Part one: {}", sum);
+use std::fs::File;
+use std::io::{self, BufReader};
+
+use dictionary::{Dictionary, Match, Source};
+use scanner::Scanner;
+
+/// Sums the calibration values of every line, noting how many lines had no numbers at all, or
+/// names the line where the sum stops fitting in a `u64`.
+fn sum_lines(input: &str, dictionary: &Dictionary) -> String {
+    let mut sum: u64 = 0;
+    let mut skipped = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        match find_and_sum(line, dictionary) {
+            Some(value) => match sum.checked_add(value) {
+                Some(total) => sum = total,
+                None => return format!("sum overflows at line {}", i + 1),
+            },
+            None => skipped += 1,
+        }
+    }
+
+    if skipped == 0 {
+        sum.to_string()
+    } else {
+        let lines = if skipped == 1 { "line" } else { "lines" };
+        format!("{} ({} {} without numbers skipped, see `report`)", sum, skipped, lines)
+    }
 }
 
-fn part_two(input: &str) {
-
-    // Not very efficient, but it works. 
-    // Replace the words with numbers, then find the first two in the same way we did in part one.
-    let replacements = [
-        ("zero", "z0o"),
-        ("one", "o1e"),
-        ("two", "t2w"),
-        ("three", "t3e"),
-        ("four", "f4r"),
-        ("five", "f5e"),
-        ("six", "s6x"),
-        ("seven", "s7n"),
-        ("eight", "e8t"),
-        ("nine", "n9e"),
-    ];
-
-    let sum = input.lines().map(|l| {
-        let line = replacements.iter().fold(l.to_string(), |acc, (from, to)| {
-            acc.replace(from, to)
-        });
-        return find_and_sum(&line);
-    }).sum::<u32>();
-
-    println!("Part two: {}", sum);
+fn part_one(input: &str, digits: &Dictionary) {
+    println!("Part one: {}", sum_lines(input, digits));
 }
 
-fn find_and_sum(line: &str) -> u32 {
-    let mut left_idx = 0;
-    let mut right_idx = line.len().saturating_sub(1);
+fn part_two(input: &str, dictionary: &Dictionary) {
+    println!("Part two: {}", sum_lines(input, dictionary));
+}
 
-    let mut left_number = None;
-    let mut right_number = None;
+/// Joins the first and last numbers as if their digits were written side by side, so `1` and `2`
+/// give `12` and `ten` and `eleven` give `1011`. Neither may be above `dictionary::MAX_VALUE`, or
+/// the result could overflow.
+fn calibration_value(first: u64, last: u64) -> u64 {
+    let mut shift = 10;
+    while shift <= last {
+        shift *= 10;
+    }
 
-        while left_idx <= right_idx {
-            left_number = line.chars().nth(left_idx).and_then(|c| c.to_digit(10));
-            right_number = line.chars().nth(right_idx).and_then(|c| c.to_digit(10));
+    first * shift + last
+}
 
-            if left_number.is_none() {
-                left_idx += 1;
-            }
+/// The calibration value of a line, or `None` when it holds no numbers.
+fn find_and_sum(line: &str, dictionary: &Dictionary) -> Option<u64> {
+    let first = dictionary.first(line)?;
+    let last = dictionary.last(line)?;
+    Some(calibration_value(first.value, last.value))
+}
 
-            if right_number.is_none() {
-                right_idx = right_idx.saturating_sub(1);
-            }
+fn describe(line: &str, found: &Match) -> String {
+    let position = line[..found.start].chars().count();
+    match &found.source {
+        Source::Digit => format!("digit {} at {}", found.value, position),
+        Source::Word(word) => format!("word \"{}\" ({}) at {}", word, found.value, position),
+    }
+}
 
-            if left_number.is_some() && right_number.is_some() {
-                break;
-            }
-        }
+/// The numbers picked as first and last in `line`, where they were found and the calibration value
+/// they make. Positions count characters, not bytes, so they line up with the text as shown.
+fn report_line(number: usize, line: &str, dictionary: &Dictionary) -> String {
+    match (dictionary.first(line), dictionary.last(line)) {
+        (Some(first), Some(last)) => format!(
+            "Line {}: {} | first {}, last {}, value {}",
+            number,
+            line,
+            describe(line, &first),
+            describe(line, &last),
+            calibration_value(first.value, last.value)
+        ),
+        _ => format!("Line {}: {} | NO NUMBERS FOUND", number, line),
+    }
+}
 
-        if let (Some(left), Some(right)) = (left_number, right_number) {
-            return format!("{}{}", left, right).parse::<u32>().unwrap();
-        } else {
-            panic!("Could not find two numbers in line: {}", line);
-        }
+/// Prints the report for every line.
+fn report(input: &str, dictionary: &Dictionary) {
+    for (i, line) in input.lines().enumerate() {
+        println!("{}", report_line(i + 1, line, dictionary));
+    }
+}
+
+/// Solves both parts for a document of any size read from `file`, or from stdin without one.
+fn stream(file: Option<&String>, digits: &Dictionary, dictionary: &Dictionary) -> io::Result<()> {
+    let scanners = [Scanner::new(digits), Scanner::new(dictionary)];
+    let totals = match file {
+        Some(file) => scanner::sum_reader(BufReader::with_capacity(1 << 20, File::open(file)?), &scanners)?,
+        None => scanner::sum_reader(io::stdin().lock(), &scanners)?,
+    };
+
+    for (part, totals) in ["one", "two"].iter().zip(totals) {
+        println!("Part {}: {} ({} lines, {} bytes, {} without numbers)", part, totals.sum, totals.lines, totals.bytes, totals.skipped);
+    }
+
+    Ok(())
+}
+
+enum Mode {
+    Parts,
+    Report,
+    Stream(Option<String>),
 }
 
+/// Part two spells numbers in English unless `--dictionary FILE` gives other words, one
+/// `word value` pair per line. `--ignore-case` matches the words case-insensitively, and
+/// `--unicode-digits` counts decimal digits from any script, such as `３` or `٣`. `report`
+/// breaks the part two sum down line by line, or the part one sum with `--digits-only`.
+/// `stream [FILE]` solves a document too big for memory, and `bench [--lines N]` measures the
+/// streaming scanner.
 fn main() {
     let input = include_str!("./input.txt");
-    part_one(input);
-    part_two(input);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().is_some_and(|arg| arg == "bench") {
+        bench::run(&args[1..]);
+        return;
+    }
+
+    let mut dictionary = Dictionary::english();
+    let mut ignore_case = false;
+    let mut unicode_digits = false;
+    let mut mode = Mode::Parts;
+
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dictionary" => {
+                let file = args.next().expect("--dictionary needs a file name");
+                let text = std::fs::read_to_string(file).unwrap_or_else(|e| panic!("Could not read {}: {}", file, e));
+                dictionary = Dictionary::parse(&text).unwrap_or_else(|e| panic!("{}", e));
+            },
+            "--ignore-case" => ignore_case = true,
+            "--unicode-digits" => unicode_digits = true,
+            "--digits-only" => dictionary = Dictionary::digits_only(),
+            "report" => mode = Mode::Report,
+            "stream" => mode = Mode::Stream(args.next_if(|arg| !arg.starts_with("--")).cloned()),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let digits = Dictionary::digits_only().unicode_digits(unicode_digits);
+    let dictionary = dictionary.ignore_case(ignore_case).unicode_digits(unicode_digits);
+
+    match mode {
+        Mode::Parts => {
+            part_one(input, &digits);
+            part_two(input, &dictionary);
+        },
+        Mode::Report => report(input, &dictionary),
+        Mode::Stream(file) => {
+            if let Err(e) = stream(file.as_ref(), &digits, &dictionary) {
+                eprintln!("Could not stream: {}", e);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_numbers_side_by_side() {
+        assert_eq!(calibration_value(1, 2), 12);
+        assert_eq!(calibration_value(3, 0), 30);
+        assert_eq!(calibration_value(10, 11), 1011);
+        assert_eq!(calibration_value(7, 100), 7100);
+        assert_eq!(calibration_value(dictionary::MAX_VALUE, dictionary::MAX_VALUE), 999_999_999_999_999_999);
+    }
+
+    #[test]
+    fn finds_multi_digit_words() {
+        let dictionary = Dictionary::parse("ten 10\neleven 11").unwrap();
+
+        assert_eq!(find_and_sum("ten then eleven", &dictionary), Some(1011));
+        assert_eq!(find_and_sum("xeleven", &dictionary), Some(1111));
+        assert_eq!(find_and_sum("nine", &dictionary), None);
+    }
+
+    #[test]
+    fn sums_the_examples() {
+        let part_one = "1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet";
+        let part_two = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen";
+
+        assert_eq!(sum_lines(part_one, &Dictionary::digits_only()), "142");
+        assert_eq!(sum_lines(part_two, &Dictionary::english()), "281");
+    }
+
+    #[test]
+    fn names_the_line_where_the_sum_overflows() {
+        let dictionary = Dictionary::parse("big 999999999").unwrap();
+        let input = vec!["big"; 19].join("\n");
+
+        assert_eq!(sum_lines(&input, &dictionary), "sum overflows at line 19");
+    }
+
+    #[test]
+    fn reports_where_numbers_were_found() {
+        let dictionary = Dictionary::english();
+
+        assert_eq!(
+            report_line(1, "two1nine", &dictionary),
+            "Line 1: two1nine | first word \"two\" (2) at 0, last word \"nine\" (9) at 4, value 29"
+        );
+        assert_eq!(report_line(4, "treb7uchet", &dictionary), "Line 4: treb7uchet | first digit 7 at 4, last digit 7 at 4, value 77");
+        assert_eq!(report_line(5, "éé3", &dictionary), "Line 5: éé3 | first digit 3 at 2, last digit 3 at 2, value 33");
+    }
+
+    #[test]
+    fn reports_lines_without_numbers() {
+        assert_eq!(report_line(2, "abc", &Dictionary::english()), "Line 2: abc | NO NUMBERS FOUND");
+        assert_eq!(report_line(3, "", &Dictionary::english()), "Line 3:  | NO NUMBERS FOUND");
+        assert_eq!(report_line(6, "onetwo", &Dictionary::digits_only()), "Line 6: onetwo | NO NUMBERS FOUND");
+    }
+
+    #[test]
+    fn notes_skipped_lines_in_the_sum() {
+        let dictionary = Dictionary::digits_only();
+
+        assert_eq!(sum_lines("1abc2\nabc\n3", &dictionary), "45 (1 line without numbers skipped, see `report`)");
+        assert_eq!(sum_lines("x\n\n7", &dictionary), "77 (2 lines without numbers skipped, see `report`)");
+    }
 }
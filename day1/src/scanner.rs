@@ -0,0 +1,304 @@
+use std::io::{self, BufRead};
+
+use crate::{calibration_value, find_and_sum};
+use crate::dictionary::Dictionary;
+
+/// A byte-oriented version of `Dictionary` for large documents. It never decodes ASCII lines and
+/// never allocates for them. Case-insensitive matching folds ASCII letters only, so when the
+/// dictionary needs more than that, lines with other text go through the dictionary itself.
+pub struct Scanner {
+    words: Vec<(Vec<u8>, u64)>,
+    ignore_case: bool,
+    /// Indices into `words` of the words starting with each byte, so most positions are rejected
+    /// with a single lookup.
+    starts: Vec<Vec<usize>>,
+    fallback: Option<Dictionary>,
+}
+
+/// Running totals of a streamed document.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Totals {
+    pub sum: u64,
+    pub lines: usize,
+    /// Lines that held no numbers and so added nothing to the sum.
+    pub skipped: usize,
+    pub bytes: usize,
+}
+
+impl Scanner {
+    pub fn new(dictionary: &Dictionary) -> Scanner {
+        let ignore_case = dictionary.ignores_case();
+        let mut starts = vec![Vec::new(); 256];
+        let mut words = Vec::new();
+
+        for (word, value) in dictionary.entries() {
+            let mut bytes = word.as_bytes().to_vec();
+            if ignore_case {
+                bytes.make_ascii_lowercase();
+            }
+
+            if let Some(&first) = bytes.first() {
+                starts[first as usize].push(words.len());
+                if ignore_case && first.is_ascii_lowercase() {
+                    starts[first.to_ascii_uppercase() as usize].push(words.len());
+                }
+                words.push((bytes, *value));
+            }
+        }
+
+        let fallback = dictionary.needs_unicode().then(|| dictionary.clone());
+
+        Scanner { words, ignore_case, starts, fallback }
+    }
+
+    /// The value of the longest word starting at `start`, if any.
+    fn word_at(&self, line: &[u8], start: usize) -> Option<u64> {
+        let rest = &line[start..];
+        self.starts[line[start] as usize]
+            .iter()
+            .map(|&index| &self.words[index])
+            .filter(|(word, _)| {
+                rest.len() >= word.len() && if self.ignore_case {
+                    rest[..word.len()].eq_ignore_ascii_case(word)
+                } else {
+                    rest.starts_with(word)
+                }
+            })
+            .max_by_key(|(word, _)| word.len())
+            .map(|(_, value)| *value)
+    }
+
+    /// Only positions before the first digit can hold an earlier word, so find the digit first.
+    pub fn first(&self, line: &[u8]) -> Option<u64> {
+        let digit = digits::first(line);
+        let limit = digit.unwrap_or(line.len());
+
+        if !self.words.is_empty() {
+            if let Some(value) = (0..limit).find_map(|start| self.word_at(line, start)) {
+                return Some(value);
+            }
+        }
+
+        digit.map(|position| (line[position] - b'0') as u64)
+    }
+
+    /// Mirrors `first`: only words starting after the last digit can come later.
+    pub fn last(&self, line: &[u8]) -> Option<u64> {
+        let digit = digits::last(line);
+        let limit = digit.map_or(0, |position| position + 1);
+
+        if !self.words.is_empty() {
+            if let Some(value) = (limit..line.len()).rev().find_map(|start| self.word_at(line, start)) {
+                return Some(value);
+            }
+        }
+
+        digit.map(|position| (line[position] - b'0') as u64)
+    }
+
+    pub fn calibration(&self, line: &[u8]) -> Option<u64> {
+        if let Some(dictionary) = self.fallback.as_ref().filter(|_| !line.is_ascii()) {
+            return find_and_sum(&String::from_utf8_lossy(line), dictionary);
+        }
+
+        Some(calibration_value(self.first(line)?, self.last(line)?))
+    }
+}
+
+/// Streams `reader` line by line through every scanner at once, reusing a single line buffer.
+pub fn sum_reader<R: BufRead>(mut reader: R, scanners: &[Scanner]) -> io::Result<Vec<Totals>> {
+    let mut totals = vec![Totals::default(); scanners.len()];
+    let mut buffer = Vec::with_capacity(256);
+
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        let mut line = buffer.as_slice();
+        if let Some(stripped) = line.strip_suffix(b"\n") {
+            line = stripped;
+        }
+        if let Some(stripped) = line.strip_suffix(b"\r") {
+            line = stripped;
+        }
+
+        for (scanner, totals) in scanners.iter().zip(totals.iter_mut()) {
+            totals.lines += 1;
+            totals.bytes += read;
+            match scanner.calibration(line) {
+                Some(value) => {
+                    totals.sum = totals.sum.checked_add(value).ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, format!("sum overflows at line {}", totals.lines))
+                    })?;
+                },
+                None => totals.skipped += 1,
+            }
+        }
+    }
+
+    Ok(totals)
+}
+
+/// Finding ASCII digits, sixteen bytes at a time with SSE2 when built with the `simd` feature on
+/// x86_64, one byte at a time otherwise.
+pub mod digits {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub const IMPLEMENTATION: &str = "sse2";
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    pub const IMPLEMENTATION: &str = "scalar";
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn digit_mask(chunk: &[u8]) -> u32 {
+        use std::arch::x86_64::*;
+
+        debug_assert_eq!(chunk.len(), 16);
+
+        // SAFETY: SSE2 is part of the x86_64 baseline, and the load reads exactly the sixteen bytes
+        // of `chunk` without any alignment requirement.
+        unsafe {
+            let bytes = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            // Bytes of 0x80 and above compare as negative, so they fail the first test.
+            let above_zero = _mm_cmpgt_epi8(bytes, _mm_set1_epi8(b'0' as i8 - 1));
+            let below_nine = _mm_cmplt_epi8(bytes, _mm_set1_epi8(b'9' as i8 + 1));
+            _mm_movemask_epi8(_mm_and_si128(above_zero, below_nine)) as u32
+        }
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub fn first(line: &[u8]) -> Option<usize> {
+        let mut chunks = line.chunks_exact(16);
+        for (i, chunk) in chunks.by_ref().enumerate() {
+            let mask = digit_mask(chunk);
+            if mask != 0 {
+                return Some(i * 16 + mask.trailing_zeros() as usize);
+            }
+        }
+
+        let tail = line.len() - chunks.remainder().len();
+        chunks.remainder().iter().position(u8::is_ascii_digit).map(|i| tail + i)
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    pub fn last(line: &[u8]) -> Option<usize> {
+        let mut chunks = line.rchunks_exact(16);
+        for (i, chunk) in chunks.by_ref().enumerate() {
+            let mask = digit_mask(chunk);
+            if mask != 0 {
+                return Some(line.len() - (i + 1) * 16 + (31 - mask.leading_zeros()) as usize);
+            }
+        }
+
+        chunks.remainder().iter().rposition(u8::is_ascii_digit)
+    }
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    pub fn first(line: &[u8]) -> Option<usize> {
+        line.iter().position(u8::is_ascii_digit)
+    }
+
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    pub fn last(line: &[u8]) -> Option<usize> {
+        line.iter().rposition(u8::is_ascii_digit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: [&str; 12] = [
+        "two1nine",
+        "eightwothree",
+        "xtwone3four",
+        "oneight",
+        "7pqrstsixteen",
+        "EightWo 3 ONE",
+        "no numbers here",
+        "",
+        "éone2 ü",
+        "٣ eight ３",
+        "FÜNF five",
+        "abcdefghijklmnopqrstuvwxyz1abcdefghijklmnopqrstuvwxyznine",
+    ];
+
+    fn assert_agrees(dictionary: &Dictionary) {
+        let scanner = Scanner::new(dictionary);
+        for line in LINES {
+            assert_eq!(scanner.calibration(line.as_bytes()), find_and_sum(line, dictionary), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_dictionary() {
+        assert_agrees(&Dictionary::digits_only());
+        assert_agrees(&Dictionary::english());
+        assert_agrees(&Dictionary::english().ignore_case(true));
+        assert_agrees(&Dictionary::parse("ten 10\neleven 11\nfünf 5").unwrap());
+    }
+
+    #[test]
+    fn falls_back_for_non_ascii_lines() {
+        let dictionary = Dictionary::english().unicode_digits(true);
+        assert_agrees(&dictionary);
+
+        let scanner = Scanner::new(&dictionary);
+        assert_eq!(scanner.calibration("٣ eight ３".as_bytes()), Some(33));
+        assert_eq!(scanner.calibration("١٢".as_bytes()), Some(12));
+    }
+
+    #[test]
+    fn skips_lines_without_numbers() {
+        let scanner = Scanner::new(&Dictionary::english());
+
+        assert_eq!(scanner.calibration(b"abc"), None);
+        assert_eq!(scanner.calibration(b""), None);
+        assert_eq!(scanner.calibration("ü".as_bytes()), None);
+    }
+
+    #[test]
+    fn sums_a_reader() {
+        let input = "two1nine\r\nabc\n4nineeightseven2\nzoneight234";
+        let scanners = [Scanner::new(&Dictionary::digits_only()), Scanner::new(&Dictionary::english())];
+        let totals = sum_reader(input.as_bytes(), &scanners).unwrap();
+
+        assert_eq!(totals[0], Totals { sum: 11 + 42 + 24, lines: 4, skipped: 1, bytes: input.len() });
+        assert_eq!(totals[1], Totals { sum: 29 + 42 + 14, lines: 4, skipped: 1, bytes: input.len() });
+    }
+
+    #[test]
+    fn names_the_line_where_the_sum_overflows() {
+        let input = vec!["big"; 19].join("\n");
+        let scanners = [Scanner::new(&Dictionary::parse("big 999999999").unwrap())];
+        let error = sum_reader(input.as_bytes(), &scanners).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "sum overflows at line 19");
+    }
+
+    #[test]
+    fn finds_digits_in_long_lines() {
+        let mut line = vec![b'x'; 40];
+        assert_eq!((digits::first(&line), digits::last(&line)), (None, None));
+
+        // Either side of the sixteen byte chunk boundaries, from both ends.
+        for (first, last) in [(0, 39), (15, 16), (16, 23), (17, 33), (31, 32), (24, 24)] {
+            line.fill(b'x');
+            line[first] = b'1';
+            line[last] = b'2';
+            assert_eq!((digits::first(&line), digits::last(&line)), (Some(first), Some(last)), "{} {}", first, last);
+        }
+    }
+
+    #[test]
+    fn finds_digits_after_non_ascii_text() {
+        let line = "éééééééééé/:09:/ééééééééé".as_bytes();
+        assert_eq!(digits::first(line), Some(22));
+        assert_eq!(digits::last(line), Some(23));
+
+        assert_eq!(digits::first("é".repeat(20).as_bytes()), None);
+        assert_eq!(digits::last(b"0123456789012345"), Some(15));
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::game::{Game, Violation};
+
+/// How much to say about each game checked against the bag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    /// Only the games the bag rules out, with the draw that rules them out.
+    Impossible,
+    /// Every game, possible or not.
+    All,
+}
+
+impl Verbosity {
+    pub fn parse(text: &str) -> Result<Verbosity, String> {
+        match text {
+            "quiet" => Ok(Verbosity::Quiet),
+            "impossible" => Ok(Verbosity::Impossible),
+            "all" => Ok(Verbosity::All),
+            _ => Err(format!("Unknown verbosity {}, expected quiet, impossible or all", text)),
+        }
+    }
+}
+
+/// Collects what part one finds out about each game, away from the answer on stdout. Messages go
+/// to stderr for people to read, or to a log file as `key=value` lines for tools to parse.
+pub struct Diagnostics {
+    verbosity: Verbosity,
+    out: Box<dyn Write>,
+    structured: bool,
+}
+
+impl Diagnostics {
+    pub fn stderr(verbosity: Verbosity) -> Diagnostics {
+        Diagnostics { verbosity, out: Box::new(io::stderr()), structured: false }
+    }
+
+    pub fn log(verbosity: Verbosity, file: &str) -> io::Result<Diagnostics> {
+        Ok(Diagnostics { verbosity, out: Box::new(BufWriter::new(File::create(file)?)), structured: true })
+    }
+
+    /// Records whether `game` was possible, and if not, the `violation` that rules it out.
+    pub fn game(&mut self, game: &Game, violation: Option<&Violation>) -> io::Result<()> {
+        match self.line(game, violation) {
+            Some(line) => writeln!(self.out, "{}", line),
+            None => Ok(()),
+        }
+    }
+
+    /// Pushes out anything still buffered for the log file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// What `game` writes about a game, or `None` when the verbosity leaves it out.
+    fn line(&self, game: &Game, violation: Option<&Violation>) -> Option<String> {
+        let wanted = if violation.is_some() { Verbosity::Impossible } else { Verbosity::All };
+        if self.verbosity < wanted {
+            return None;
+        }
+
+        let line = match (violation, self.structured) {
+            (None, false) => format!("Game {} is possible", game.id),
+            (None, true) => format!("game={} possible=true", game.id),
+            (Some(v), false) => format!(
+                "Game {} is not possible: draw {} shows {} {}, but the bag holds {}",
+                game.id, v.draw, v.shown, v.colour, v.limit
+            ),
+            (Some(v), true) => format!(
+                "game={} possible=false draw={} colour={} shown={} limit={}",
+                game.id, v.draw, v.colour, v.shown, v.limit
+            ),
+        };
+
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(verbosity: Verbosity, structured: bool) -> Diagnostics {
+        Diagnostics { verbosity, out: Box::new(io::sink()), structured }
+    }
+
+    #[test]
+    fn parses_verbosity() {
+        assert_eq!(Verbosity::parse("quiet"), Ok(Verbosity::Quiet));
+        assert_eq!(Verbosity::parse("impossible"), Ok(Verbosity::Impossible));
+        assert_eq!(Verbosity::parse("all"), Ok(Verbosity::All));
+        assert_eq!(Verbosity::parse("loud"), Err("Unknown verbosity loud, expected quiet, impossible or all".to_string()));
+    }
+
+    #[test]
+    fn describes_games_as_verbose_as_asked() {
+        let game = Game::parse("Game 3: 20 red").unwrap();
+        let violation = Violation { draw: 1, colour: "red".to_string(), shown: 20, limit: 12 };
+
+        assert_eq!(diagnostics(Verbosity::Quiet, false).line(&game, Some(&violation)), None);
+        assert_eq!(diagnostics(Verbosity::Impossible, false).line(&game, None), None);
+        assert_eq!(
+            diagnostics(Verbosity::Impossible, false).line(&game, Some(&violation)),
+            Some("Game 3 is not possible: draw 1 shows 20 red, but the bag holds 12".to_string())
+        );
+        assert_eq!(diagnostics(Verbosity::All, false).line(&game, None), Some("Game 3 is possible".to_string()));
+    }
+
+    #[test]
+    fn logs_key_value_lines() {
+        let game = Game::parse("Game 3: 20 red").unwrap();
+        let violation = Violation { draw: 1, colour: "red".to_string(), shown: 20, limit: 12 };
+        let log = diagnostics(Verbosity::All, true);
+
+        assert_eq!(log.line(&game, None), Some("game=3 possible=true".to_string()));
+        assert_eq!(
+            log.line(&game, Some(&violation)),
+            Some("game=3 possible=false draw=1 colour=red shown=20 limit=12".to_string())
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A handful of cubes shown at once, keyed by colour. Colours that were not shown count as zero. A
+/// bag is described the same way, by how many cubes of each colour it holds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Draw {
+    counts: BTreeMap<String, u32>,
+}
+
+impl Draw {
+    /// Parses a draw such as `3 blue, 4 red`. Any single word names a colour.
+    pub fn parse(text: &str) -> Result<Draw, String> {
+        let mut counts = BTreeMap::new();
+
+        for pull in text.split(',').map(|pull| pull.trim()) {
+            let (count, colour) = pull
+                .split_once(' ')
+                .filter(|(_, colour)| !colour.is_empty() && !colour.contains(char::is_whitespace))
+                .ok_or_else(|| format!("Expected `count colour`, got \"{}\"", pull))?;
+            let count = count.parse::<u32>().map_err(|_| format!("Invalid count in \"{}\"", pull))?;
+
+            if counts.insert(colour.to_string(), count).is_some() {
+                return Err(format!("Colour {} appears twice in \"{}\"", colour, text.trim()));
+            }
+        }
+
+        Ok(Draw { counts })
+    }
+
+    pub fn count(&self, colour: &str) -> u32 {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = &String> {
+        self.counts.keys()
+    }
+
+    /// Whether this draw could have come out of `bag`.
+    pub fn fits(&self, bag: &Draw) -> bool {
+        self.counts.iter().all(|(colour, &count)| count <= bag.count(colour))
+    }
+
+    /// The smallest draw holding at least as many cubes of every colour as both `self` and `other`.
+    fn union(&mut self, other: &Draw) {
+        for (colour, &count) in other.counts.iter() {
+            let entry = self.counts.entry(colour.to_string()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+}
+
+impl fmt::Display for Draw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pulls: Vec<String> = self.counts.iter().map(|(colour, count)| format!("{} {}", count, colour)).collect();
+        write!(f, "{}", pulls.join(", "))
+    }
+}
+
+/// Where a game first asks for more cubes than a bag holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The offending draw, counting from one.
+    pub draw: usize,
+    pub colour: String,
+    pub shown: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: u32,
+    pub draws: Vec<Draw>,
+}
+
+impl Game {
+    /// Parses a line such as `Game 1: 3 blue, 4 red; 1 red, 2 green`.
+    pub fn parse(line: &str) -> Result<Game, String> {
+        let (name, draws) = line.split_once(':').ok_or("Missing `:` after the game id")?;
+        let id = name
+            .trim()
+            .strip_prefix("Game ")
+            .and_then(|id| id.trim().parse::<u32>().ok())
+            .ok_or_else(|| format!("Expected `Game <id>`, got \"{}\"", name))?;
+        let draws = draws.split(';').map(Draw::parse).collect::<Result<_, _>>()?;
+
+        Ok(Game { id, draws })
+    }
+
+    /// The most cubes of `colour` shown in any one draw.
+    pub fn max(&self, colour: &str) -> u32 {
+        self.draws.iter().map(|draw| draw.count(colour)).max().unwrap_or(0)
+    }
+
+    /// The smallest bag every draw of the game fits in.
+    pub fn minimal_bag(&self) -> Draw {
+        let mut bag = Draw::default();
+        for draw in self.draws.iter() {
+            bag.union(draw);
+        }
+        bag
+    }
+
+    pub fn is_possible(&self, bag: &Draw) -> bool {
+        self.draws.iter().all(|draw| draw.fits(bag))
+    }
+
+    /// The first colour, in the first draw, that shows more cubes than `bag` holds.
+    pub fn first_violation(&self, bag: &Draw) -> Option<Violation> {
+        self.draws.iter().enumerate().find_map(|(i, draw)| {
+            draw.counts
+                .iter()
+                .find(|(colour, &shown)| shown > bag.count(colour))
+                .map(|(colour, &shown)| Violation { draw: i + 1, colour: colour.to_string(), shown, limit: bag.count(colour) })
+        })
+    }
+
+    /// The number of cubes of each of `colours` in the minimal bag, multiplied together. A colour
+    /// the game never shows makes the power zero.
+    pub fn power(&self, colours: &[String]) -> u64 {
+        colours.iter().map(|colour| self.max(colour) as u64).product()
+    }
+}
+
+/// Parses every game, naming the line of the first one that fails.
+pub fn parse_games(input: &str) -> Result<Vec<Game>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Game::parse(line).map_err(|e| format!("Line {}: {}", i + 1, e)))
+        .collect()
+}
+
+/// Every colour shown in any game, in alphabetical order.
+pub fn colours(games: &[Game]) -> Vec<String> {
+    let colours: BTreeSet<&String> = games.iter().flat_map(|game| game.draws.iter()).flat_map(Draw::colours).collect();
+    colours.into_iter().cloned().collect()
+}
+
+/// The smallest bag that every game could have been played with.
+pub fn minimal_bag(games: &[Game]) -> Draw {
+    let mut bag = Draw::default();
+    for game in games.iter() {
+        bag.union(&game.minimal_bag());
+    }
+    bag
+}
+
+/// Reads a bag from a file in the same `count colour` form as a draw. Pulls may be split over
+/// several lines, and lines starting with `#` are skipped.
+pub fn read_bag(text: &str) -> Result<Draw, String> {
+    let pulls: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    Draw::parse(&pulls.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    fn draw(text: &str) -> Draw {
+        Draw::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_draws() {
+        let parsed = draw(" 3 blue, 4 red ");
+        assert_eq!(parsed.count("blue"), 3);
+        assert_eq!(parsed.count("red"), 4);
+        assert_eq!(parsed.count("green"), 0);
+        assert_eq!(parsed.to_string(), "3 blue, 4 red");
+    }
+
+    #[test]
+    fn rejects_malformed_draws() {
+        assert_eq!(Draw::parse("3").unwrap_err(), "Expected `count colour`, got \"3\"");
+        assert_eq!(Draw::parse("3 light blue").unwrap_err(), "Expected `count colour`, got \"3 light blue\"");
+        assert_eq!(Draw::parse("3 blue, ").unwrap_err(), "Expected `count colour`, got \"\"");
+        assert_eq!(Draw::parse("x blue").unwrap_err(), "Invalid count in \"x blue\"");
+        assert_eq!(Draw::parse("-1 blue").unwrap_err(), "Invalid count in \"-1 blue\"");
+        assert_eq!(Draw::parse("3 blue, 1 red, 2 blue").unwrap_err(), "Colour blue appears twice in \"3 blue, 1 red, 2 blue\"");
+    }
+
+    #[test]
+    fn parses_games() {
+        let game = Game::parse("Game 12: 3 blue, 4 red; 1 red").unwrap();
+        assert_eq!(game.id, 12);
+        assert_eq!(game.draws, [draw("3 blue, 4 red"), draw("1 red")]);
+        assert_eq!(parse_games(EXAMPLE).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn rejects_malformed_games() {
+        assert_eq!(Game::parse("Game 1 3 blue").unwrap_err(), "Missing `:` after the game id");
+        assert_eq!(Game::parse("Round 1: 3 blue").unwrap_err(), "Expected `Game <id>`, got \"Round 1\"");
+        assert_eq!(Game::parse("Game one: 3 blue").unwrap_err(), "Expected `Game <id>`, got \"Game one\"");
+        assert_eq!(Game::parse("Game 1: 3 blue;").unwrap_err(), "Expected `count colour`, got \"\"");
+        assert_eq!(parse_games("Game 1: 3 blue\nGame 2: blue").unwrap_err(), "Line 2: Expected `count colour`, got \"blue\"");
+    }
+
+    #[test]
+    fn finds_the_minimal_bag() {
+        let games = parse_games(EXAMPLE).unwrap();
+
+        assert_eq!(games[0].minimal_bag(), draw("4 red, 2 green, 6 blue"));
+        assert_eq!(games[2].max("red"), 20);
+        assert_eq!(games[2].max("yellow"), 0);
+        assert_eq!(minimal_bag(&games), draw("20 red, 13 green, 15 blue"));
+    }
+
+    #[test]
+    fn checks_games_against_a_bag() {
+        let games = parse_games(EXAMPLE).unwrap();
+        let bag = draw("12 red, 13 green, 14 blue");
+
+        let possible: Vec<u32> = games.iter().filter(|game| game.is_possible(&bag)).map(|game| game.id).collect();
+        assert_eq!(possible, [1, 2, 5]);
+        assert!(draw("2 green").fits(&bag));
+        assert!(!draw("1 yellow").fits(&bag));
+    }
+
+    #[test]
+    fn finds_the_first_violation() {
+        let games = parse_games(EXAMPLE).unwrap();
+        let bag = draw("12 red, 13 green, 14 blue");
+
+        assert_eq!(games[0].first_violation(&bag), None);
+        assert_eq!(
+            games[2].first_violation(&bag),
+            Some(Violation { draw: 1, colour: "red".to_string(), shown: 20, limit: 12 })
+        );
+        assert_eq!(
+            games[3].first_violation(&bag),
+            Some(Violation { draw: 3, colour: "blue".to_string(), shown: 15, limit: 14 })
+        );
+        // A colour the bag does not mention holds no cubes.
+        assert_eq!(
+            Game::parse("Game 6: 1 red; 2 yellow").unwrap().first_violation(&bag),
+            Some(Violation { draw: 2, colour: "yellow".to_string(), shown: 2, limit: 0 })
+        );
+    }
+
+    #[test]
+    fn multiplies_any_colours_into_the_power() {
+        let games = parse_games(EXAMPLE).unwrap();
+        let standard = colours(&games);
+        assert_eq!(standard, ["blue", "green", "red"]);
+
+        let powers: Vec<u64> = games.iter().map(|game| game.power(&standard)).collect();
+        assert_eq!(powers, [48, 12, 1560, 630, 36]);
+
+        let game = Game::parse("Game 1: 2 cyan, 3 magenta; 5 cyan, 1 yellow").unwrap();
+        let colours = colours(std::slice::from_ref(&game));
+        assert_eq!(colours, ["cyan", "magenta", "yellow"]);
+        assert_eq!(game.power(&colours), 15);
+        assert_eq!(game.power(&["cyan".to_string(), "black".to_string()]), 0);
+        assert_eq!(game.power(&[]), 1);
+    }
+
+    #[test]
+    fn reads_bag_files() {
+        let bag = read_bag("# The elf's bag\n12 red,\n13 green, 14 blue\n\n2 yellow\n").unwrap();
+        assert_eq!(bag, draw("12 red, 13 green, 14 blue, 2 yellow"));
+
+        assert_eq!(read_bag("12 red\n3 red").unwrap_err(), "Colour red appears twice in \"12 red, 3 red\"");
+        assert_eq!(read_bag("12").unwrap_err(), "Expected `count colour`, got \"12\"");
+    }
+}
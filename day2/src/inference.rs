@@ -0,0 +1,279 @@
+use crate::game::{Draw, Game};
+
+/// Natural logarithms of `0!` to `n!`, so binomial coefficients never overflow.
+struct LnFactorials(Vec<f64>);
+
+impl LnFactorials {
+    fn up_to(n: u32) -> LnFactorials {
+        let mut table = vec![0.0; n as usize + 1];
+        for i in 1..table.len() {
+            table[i] = table[i - 1] + (i as f64).ln();
+        }
+        LnFactorials(table)
+    }
+
+    fn ln_choose(&self, n: u32, k: u32) -> f64 {
+        self.0[n as usize] - self.0[k as usize] - self.0[(n - k) as usize]
+    }
+}
+
+/// Cube counts per colour, in the order of the colour list they were built from.
+type Counts = Vec<u32>;
+
+fn counts(draw: &Draw, colours: &[String]) -> Counts {
+    colours.iter().map(|colour| draw.count(colour)).collect()
+}
+
+/// The log-probability of pulling exactly `draw` out of `bag` in one handful, without replacement:
+/// the multivariate hypergeometric distribution. `None` when the bag cannot hold the draw.
+fn ln_likelihood(bag: &[u32], draw: &[u32], table: &LnFactorials) -> Option<f64> {
+    if draw.iter().zip(bag).any(|(drawn, held)| drawn > held) {
+        return None;
+    }
+
+    let ways: f64 = bag.iter().zip(draw).map(|(&held, &drawn)| table.ln_choose(held, drawn)).sum();
+    let all = table.ln_choose(bag.iter().sum(), draw.iter().sum());
+    Some(ways - all)
+}
+
+/// The most bags `estimate` will weigh for one game. Ten colours and sixty cubes would already be
+/// hundreds of billions.
+const MAX_BAGS: u128 = 10_000_000;
+
+/// How many bags `for_each_bag` visits: spreading up to `spare` extra cubes over `colours` colours
+/// can be done in `C(spare + colours, colours)` ways. Saturates rather than overflowing.
+fn bag_count(colours: usize, spare: u32) -> u128 {
+    let mut count: u128 = 1;
+    for i in 1..=colours as u128 {
+        // Each step leaves C(spare + i, i), so the division is exact.
+        count = match count.checked_mul(spare as u128 + i) {
+            Some(product) => product / i,
+            None => return u128::MAX,
+        };
+    }
+    count
+}
+
+/// Calls `visit` with every bag holding at least `minimum` cubes of each colour and at most
+/// `max_total` cubes altogether.
+fn for_each_bag(minimum: &[u32], max_total: u32, visit: &mut impl FnMut(&[u32])) {
+    fn fill(bag: &mut Counts, minimum: &[u32], spare: u32, visit: &mut impl FnMut(&[u32])) {
+        let colour = bag.len();
+        if colour == minimum.len() {
+            visit(bag);
+            return;
+        }
+
+        for extra in 0..=spare {
+            bag.push(minimum[colour] + extra);
+            fill(bag, minimum, spare - extra, visit);
+            bag.pop();
+        }
+    }
+
+    let needed: u32 = minimum.iter().sum();
+    if needed <= max_total {
+        fill(&mut Vec::with_capacity(minimum.len()), minimum, max_total - needed, visit);
+    }
+}
+
+/// What the draws of one game say about the bag it was played with.
+pub struct Estimate {
+    /// The smallest bag that could have produced the draws.
+    pub minimal: Counts,
+    /// The bag under which the draws were most likely, preferring smaller bags on ties.
+    pub most_likely: Counts,
+    pub most_likely_probability: f64,
+    /// Expected cubes of each colour, given the draws and a uniform prior over every bag of up to
+    /// `max_total` cubes.
+    pub posterior_mean: Vec<f64>,
+    /// Posterior probability that the bag held no more cubes of any colour than the hypothesis.
+    pub within_hypothesis: f64,
+    /// The probability of seeing exactly these draws from the hypothesised bag, zero if it could
+    /// not have produced them.
+    pub hypothesis_probability: f64,
+}
+
+/// Estimates the bag behind `game`, assuming every draw is a single handful taken without
+/// replacement and the cubes go back in the bag between draws. Only bags of at most `max_total`
+/// cubes are considered, which also bounds the prior, though the minimal bag always is. Refuses
+/// when that leaves more than `MAX_BAGS` bags to weigh.
+pub fn estimate(game: &Game, colours: &[String], hypothesis: &Draw, max_total: u32) -> Result<Estimate, String> {
+    let draws: Vec<Counts> = game.draws.iter().map(|draw| counts(draw, colours)).collect();
+    let minimal = counts(&game.minimal_bag(), colours);
+    let needed: u32 = minimal.iter().sum();
+    let max_total = max_total.max(needed);
+
+    let count = bag_count(colours.len(), max_total - needed);
+    if count > MAX_BAGS {
+        return Err(format!(
+            "too many bags to weigh ({} over {} colours with up to {} cubes, the limit is {}); try a lower --max-cubes",
+            if count == u128::MAX { "more than 2^128".to_string() } else { count.to_string() },
+            colours.len(),
+            max_total,
+            MAX_BAGS
+        ));
+    }
+
+    let hypothesis = counts(hypothesis, colours);
+    let table = LnFactorials::up_to(max_total.max(hypothesis.iter().sum()));
+
+    let ln_game = |bag: &[u32]| draws.iter().map(|draw| ln_likelihood(bag, draw, &table)).sum::<Option<f64>>();
+
+    // Log-likelihoods are shifted by the best one before exponentiating so the weights stay finite.
+    let mut bags = Vec::new();
+    for_each_bag(&minimal, max_total, &mut |bag| {
+        if let Some(ln) = ln_game(bag) {
+            bags.push((bag.to_vec(), ln));
+        }
+    });
+
+    let (most_likely, best) = bags
+        .iter()
+        .fold((minimal.clone(), f64::NEG_INFINITY), |(best_bag, best), (bag, ln)| {
+            if *ln > best { (bag.clone(), *ln) } else { (best_bag, best) }
+        });
+
+    let mut total = 0.0;
+    let mut within = 0.0;
+    let mut mean = vec![0.0; colours.len()];
+    for (bag, ln) in bags.iter() {
+        let weight = (ln - best).exp();
+        total += weight;
+        if bag.iter().zip(&hypothesis).all(|(held, limit)| held <= limit) {
+            within += weight;
+        }
+        for (mean, &held) in mean.iter_mut().zip(bag) {
+            *mean += weight * held as f64;
+        }
+    }
+
+    Ok(Estimate {
+        minimal,
+        most_likely,
+        most_likely_probability: best.exp(),
+        posterior_mean: mean.iter().map(|mean| mean / total).collect(),
+        within_hypothesis: if total > 0.0 { within / total } else { 0.0 },
+        hypothesis_probability: ln_game(&hypothesis).map_or(0.0, f64::exp),
+    })
+}
+
+fn describe(counts: &[u32], colours: &[String]) -> String {
+    let pulls: Vec<String> = counts.iter().zip(colours).map(|(count, colour)| format!("{} {}", count, colour)).collect();
+    pulls.join(", ")
+}
+
+/// Estimates each game's bag and weighs it against the hypothesised `bag`.
+pub fn run(games: &[Game], colours: &[String], bag: &Draw, args: &[String]) {
+    let mut max_total = 60;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-cubes" => max_total = args.next().and_then(|n| n.parse().ok()).expect("--max-cubes needs a number"),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    println!("Hypothesis: {}; bags of up to {} cubes, uniform prior", bag, max_total);
+
+    for game in games.iter() {
+        let estimate = match estimate(game, colours, bag, max_total) {
+            Ok(estimate) => estimate,
+            Err(e) => {
+                println!("Game {}: skipped, {}", game.id, e);
+                continue;
+            }
+        };
+        let mean: Vec<String> = estimate
+            .posterior_mean
+            .iter()
+            .zip(colours)
+            .map(|(mean, colour)| format!("{:.1} {}", mean, colour))
+            .collect();
+
+        // A most likely bag at the limit means a bigger one would probably do better still.
+        let limited = if estimate.most_likely.iter().sum::<u32>() >= max_total { ", at the cube limit" } else { "" };
+
+        println!("Game {}:", game.id);
+        println!("  minimal bag:    {}", describe(&estimate.minimal, colours));
+        println!(
+            "  most likely:    {} (draws have probability {:.3e}{})",
+            describe(&estimate.most_likely, colours),
+            estimate.most_likely_probability,
+            limited
+        );
+        println!("  posterior mean: {}", mean.join(", "));
+        println!(
+            "  hypothesis:     draws have probability {:.3e}, bag fits within it with probability {:.3}",
+            estimate.hypothesis_probability,
+            estimate.within_hypothesis
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colours(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn ln_likelihood_of_a_tiny_bag() {
+        let table = LnFactorials::up_to(5);
+
+        // Two red and one blue: one of each is 2 of the C(3, 2) = 3 possible pairs.
+        let ln = ln_likelihood(&[2, 1], &[1, 1], &table).unwrap();
+        assert!((ln.exp() - 2.0 / 3.0).abs() < 1e-12);
+
+        // Both reds is the remaining pair.
+        let ln = ln_likelihood(&[2, 1], &[2, 0], &table).unwrap();
+        assert!((ln.exp() - 1.0 / 3.0).abs() < 1e-12);
+
+        assert_eq!(ln_likelihood(&[2, 1], &[0, 0], &table), Some(0.0));
+        assert_eq!(ln_likelihood(&[2, 1], &[0, 2], &table), None);
+    }
+
+    #[test]
+    fn counts_the_bags_it_visits() {
+        let mut visited = Vec::new();
+        for_each_bag(&[1, 0], 3, &mut |bag| visited.push(bag.to_vec()));
+
+        assert_eq!(visited, [[1, 0], [1, 1], [1, 2], [2, 0], [2, 1], [3, 0]]);
+        assert_eq!(bag_count(2, 2), 6);
+
+        let mut visited = 0;
+        for_each_bag(&[2, 1, 0, 3], 14, &mut |_| visited += 1);
+        assert_eq!(bag_count(4, 8), visited);
+
+        assert_eq!(bag_count(0, 10), 1);
+        assert_eq!(bag_count(10, 60), 396_704_524_216);
+        assert_eq!(bag_count(200, u32::MAX), u128::MAX);
+    }
+
+    #[test]
+    fn estimates_a_single_colour_bag() {
+        let game = Game::parse("Game 1: 1 red").unwrap();
+        let estimate = estimate(&game, &colours(&["red"]), &Draw::parse("1 red").unwrap(), 3).unwrap();
+
+        // Every all-red bag gives the draw for certain, so the smallest wins the tie.
+        assert_eq!(estimate.minimal, [1]);
+        assert_eq!(estimate.most_likely, [1]);
+        assert_eq!(estimate.most_likely_probability, 1.0);
+        assert!((estimate.posterior_mean[0] - 2.0).abs() < 1e-12);
+        assert!((estimate.within_hypothesis - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(estimate.hypothesis_probability, 1.0);
+    }
+
+    #[test]
+    fn refuses_too_many_bags() {
+        let names = colours(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        let game = Game::parse("Game 7: 1 a, 1 b").unwrap();
+
+        let error = estimate(&game, &names, &Draw::default(), 60).err().unwrap();
+        assert!(error.starts_with("too many bags to weigh (290752384208 over 10 colours"), "{}", error);
+        assert!(estimate(&game, &names, &Draw::default(), 12).is_ok());
+    }
+}
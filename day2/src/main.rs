@@ -1,90 +1,122 @@
-fn part_one(input: &str) {
-    let red_count = 12;
-    let green_count = 13;
-    let blue_count = 14;
-
-    let possible_games = input.lines().map(|line| {
-        let mut line_parts = line.split(":");
-        let game_id: u16 = line_parts.next().unwrap().replace("Game ", "").parse().unwrap();
-        let mut game_parts = line_parts.next().unwrap().split(";");
-
-        if game_parts.all(|game_part| {
-
-            let mut possible = true;
-            
-           game_part.trim().split(", ").for_each(|pull| {
-
-                let mut red: u16 = 0;
-                let mut green: u16 = 0;
-                let mut blue: u16 = 0;
-
-                let pull_parts: Vec<&str> = pull.split(' ').collect();
-
-                match pull_parts[1] {
-                    "red" => red += pull_parts[0].parse::<u16>().unwrap(),
-                    "green" => green += pull_parts[0].parse::<u16>().unwrap(),
-                    "blue" => blue += pull_parts[0].parse::<u16>().unwrap(),
-                    _ => println!("Unknown color")
-                }
-                
-                if (red > red_count) || (green > green_count) || (blue > blue_count) {
-                    println!("Game {} is not possible", game_id);
-                    possible = false;
-                }
-            });
-
-            return possible;
-        })  {
-            return game_id
+mod diagnostics;
+mod game;
+mod inference;
+
+use diagnostics::{Diagnostics, Verbosity};
+use game::{Draw, Game};
+use std::io;
+
+const DEFAULT_BAG: &str = "12 red, 13 green, 14 blue";
+
+/// Sums the ids of the games `bag` allows, telling `diagnostics` about each game on the way.
+fn part_one(games: &[Game], bag: &Draw, diagnostics: &mut Diagnostics) -> io::Result<()> {
+    let mut possible_games = 0;
+    for game in games.iter() {
+        let violation = game.first_violation(bag);
+        diagnostics.game(game, violation.as_ref())?;
+        if violation.is_none() {
+            possible_games += game.id;
         }
-        
-        return 0;
-    }).sum::<u16>();
+    }
+    diagnostics.flush()?;
 
     println!("Sum of possible game id's {}", possible_games);
+    Ok(())
 }
 
-fn part_two(input: &str) {
-    let power: u32 = input.lines().map(|line| {
-        let mut line_parts = line.split(":");
-        let game_parts = line_parts.nth(1).unwrap().split(";");
-        let mut game_max_red: u16 = 0;
-        let mut game_max_green: u16 = 0;
-        let mut game_max_blue: u16 = 0;
-
-        game_parts.for_each(|game_part| {
-
-           game_part.trim().split(", ").for_each(|pull| {
-
-                let mut red: u16 = 0;
-                let mut green: u16 = 0;
-                let mut blue: u16 = 0;
-
-                let pull_parts: Vec<&str> = pull.split(' ').collect();
+fn part_two(games: &[Game], colours: &[String]) {
+    let power = games.iter().map(|game| game.power(colours)).sum::<u64>();
 
-                match pull_parts[1] {
-                    "red" => red += pull_parts[0].parse::<u16>().unwrap(),
-                    "green" => green += pull_parts[0].parse::<u16>().unwrap(),
-                    "blue" => blue += pull_parts[0].parse::<u16>().unwrap(),
-                    _ => println!("Unknown color")
-                }
-
-                game_max_blue = game_max_blue.max(blue);
-                game_max_green = game_max_green.max(green);
-                game_max_red = game_max_red.max(red);
-            });
-        });
-
-        return game_max_blue as u32 * game_max_green as u32 * game_max_red as u32;
+    println!("Sum of the games power is {}", power);
+}
 
-    }).sum::<u32>();
+/// Lists the games that could have been played with `bag`.
+fn possible(games: &[Game], bag: &Draw) {
+    println!("Bag: {}", bag);
+    let ids: Vec<String> = games.iter().filter(|game| game.is_possible(bag)).map(|game| game.id.to_string()).collect();
+    println!("Possible games ({} of {}): {}", ids.len(), games.len(), ids.join(", "));
+}
 
-    println!("Sum of the games power is {}", power);
+fn minimal(games: &[Game]) {
+    println!("Minimal bag for all games: {}", game::minimal_bag(games));
 }
 
+enum Query {
+    Parts,
+    Report,
+    Possible,
+    Minimal,
+    Infer(Vec<String>),
+}
 
+/// The bag holds 12 red, 13 green and 14 blue cubes unless `--bag "10 red, 3 yellow"` or
+/// `--bag-file FILE` says otherwise. Colours are whatever the games show, and part two multiplies
+/// together every one of them. `possible` lists the games the bag allows, and `minimal` prints the
+/// smallest bag every game fits in. `infer [--max-cubes N]` estimates the bag behind each game and
+/// how likely it is to fit in the given one. `report` runs part one and explains on stderr why
+/// each impossible game is ruled out; `--verbosity quiet|impossible|all` says how much to explain
+/// and `--log FILE` writes it as `key=value` lines instead.
 fn main() {
     let input = include_str!("./input.txt");
-    part_one(input);
-    part_two(input);
+
+    let games = match game::parse_games(input) {
+        Ok(games) => games,
+        Err(e) => {
+            eprintln!("Invalid games: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut bag = Draw::parse(DEFAULT_BAG).unwrap();
+    let mut query = Query::Parts;
+    let mut verbosity = None;
+    let mut log = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bag" => {
+                let text = args.next().expect("--bag needs cube counts such as \"12 red, 13 green\"");
+                bag = Draw::parse(&text).unwrap_or_else(|e| panic!("Invalid bag: {}", e));
+            },
+            "--bag-file" => {
+                let file = args.next().expect("--bag-file needs a file name");
+                let text = std::fs::read_to_string(&file).unwrap_or_else(|e| panic!("Could not read {}: {}", file, e));
+                bag = game::read_bag(&text).unwrap_or_else(|e| panic!("Invalid bag in {}: {}", file, e));
+            },
+            "--verbosity" => {
+                let level = args.next().expect("--verbosity needs quiet, impossible or all");
+                verbosity = Some(Verbosity::parse(&level).unwrap_or_else(|e| panic!("{}", e)));
+            },
+            "--log" => log = Some(args.next().expect("--log needs a file name")),
+            "report" => query = Query::Report,
+            "possible" => query = Query::Possible,
+            "minimal" => query = Query::Minimal,
+            "infer" => query = Query::Infer(args.by_ref().collect()),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    // Part one keeps quiet unless asked, while the report explains impossible games by default.
+    let default = if matches!(query, Query::Report) { Verbosity::Impossible } else { Verbosity::Quiet };
+    let verbosity = verbosity.unwrap_or(default);
+    let mut diagnostics = match log {
+        Some(file) => Diagnostics::log(verbosity, &file).unwrap_or_else(|e| panic!("Could not create {}: {}", file, e)),
+        None => Diagnostics::stderr(verbosity),
+    };
+
+    match query {
+        Query::Parts | Query::Report => {
+            if let Err(e) = part_one(&games, &bag, &mut diagnostics) {
+                eprintln!("Could not write diagnostics: {}", e);
+                std::process::exit(1);
+            }
+            if matches!(query, Query::Parts) {
+                part_two(&games, &game::colours(&games));
+            }
+        },
+        Query::Possible => possible(&games, &bag),
+        Query::Minimal => minimal(&games),
+        Query::Infer(args) => inference::run(&games, &game::colours(&games), &bag, &args),
+    }
 }
@@ -0,0 +1,190 @@
+use crate::grid::{Grid, Point};
+use crate::{find_adjacent, EnginePart};
+
+/// How many parts a gear symbol needs around it to count as a gear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartCount {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl PartCount {
+    /// Parses `2` as exactly two parts and `2+` as at least two.
+    pub fn parse(text: &str) -> Result<PartCount, String> {
+        let invalid = || format!("Expected a part count such as 2 or 2+, got {}", text);
+
+        match text.strip_suffix('+') {
+            Some(count) => count.parse().map(PartCount::AtLeast).map_err(|_| invalid()),
+            None => text.parse().map(PartCount::Exactly).map_err(|_| invalid()),
+        }
+    }
+
+    fn allows(self, parts: usize) -> bool {
+        match self {
+            PartCount::Exactly(count) => parts == count,
+            PartCount::AtLeast(count) => parts >= count,
+        }
+    }
+}
+
+/// How the values of a gear's parts make its ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Product,
+    Sum,
+}
+
+impl Combine {
+    pub fn parse(text: &str) -> Result<Combine, String> {
+        match text {
+            "product" => Ok(Combine::Product),
+            "sum" => Ok(Combine::Sum),
+            _ => Err(format!("Unknown way to combine parts {}, expected product or sum", text)),
+        }
+    }
+}
+
+/// Which symbols are gears and what they are worth. The puzzle's rule is a `*` touching exactly two
+/// parts, worth their product.
+#[derive(Debug, Clone)]
+pub struct GearRule {
+    pub symbols: Vec<char>,
+    pub parts: PartCount,
+    pub combine: Combine,
+}
+
+impl Default for GearRule {
+    fn default() -> GearRule {
+        GearRule { symbols: vec!['*'], parts: PartCount::Exactly(2), combine: Combine::Product }
+    }
+}
+
+impl GearRule {
+    pub fn qualifies(&self, gear: &Gear) -> bool {
+        self.parts.allows(gear.parts.len())
+    }
+
+    pub fn ratio(&self, gear: &Gear) -> usize {
+        let values = gear.parts.iter().map(|part| part.value);
+        match self.combine {
+            Combine::Product => values.fold(1, |ratio, value| {
+                ratio.checked_mul(value).unwrap_or_else(|| panic!("Gear ratio at {:?} overflows", gear.position))
+            }),
+            Combine::Sum => values.sum(),
+        }
+    }
+}
+
+/// A gear symbol and the parts touching it, whether or not there are the right number of them.
+#[derive(Debug)]
+pub struct Gear<'a> {
+    pub position: Point,
+    pub symbol: char,
+    pub parts: Vec<&'a EnginePart>,
+}
+
+/// Every symbol the rule treats as a gear, in reading order, with the parts around it. Only
+/// characters that are symbols in the first place can be gears.
+pub fn candidates<'a>(schematic: &Grid<char>, parts: &'a [EnginePart], symbols: &[(char, Point)], rule: &GearRule) -> Vec<Gear<'a>> {
+    let mut gears: Grid<Option<Vec<&EnginePart>>> = Grid::new(schematic.width(), schematic.height(), None);
+
+    for &(_, point) in symbols.iter().filter(|(c, _)| rule.symbols.contains(c)) {
+        gears[point] = Some(Vec::new());
+    }
+
+    parts
+        .iter()
+        .for_each(|p| find_adjacent(p, &mut gears));
+
+    let positions: Vec<Point> = gears.points().collect();
+    positions
+        .into_iter()
+        .filter_map(|position| {
+            let parts = gears[position].take()?;
+            Some(Gear { position, symbol: schematic[position], parts })
+        })
+        .collect()
+}
+
+/// The lines `list` prints: every gear with its position, parts and ratio, a total, then the gear
+/// symbols that did not have the right number of parts.
+fn describe(schematic: &Grid<char>, parts: &[EnginePart], symbols: &[(char, Point)], rule: &GearRule) -> Vec<String> {
+    let (gears, rejected): (Vec<Gear>, Vec<Gear>) =
+        candidates(schematic, parts, symbols, rule).into_iter().partition(|gear| rule.qualifies(gear));
+    let mut lines = Vec::new();
+
+    for gear in gears.iter() {
+        let values: Vec<String> = gear.parts.iter().map(|part| part.value.to_string()).collect();
+        lines.push(format!(
+            "Gear {} at line {}, column {}: {} -> {}",
+            gear.symbol,
+            gear.position.row + 1,
+            gear.position.col + 1,
+            values.join(", "),
+            rule.ratio(gear)
+        ));
+    }
+
+    lines.push(format!("{} gears, ratios sum to {}", gears.len(), gears.iter().map(|gear| rule.ratio(gear)).sum::<usize>()));
+
+    for gear in rejected.iter() {
+        let noun = if gear.parts.len() == 1 { "part" } else { "parts" };
+        lines.push(format!(
+            "Not a gear: {} at line {}, column {} touches {} {}",
+            gear.symbol,
+            gear.position.row + 1,
+            gear.position.col + 1,
+            gear.parts.len(),
+            noun
+        ));
+    }
+
+    lines
+}
+
+/// Lists every gear the rule finds, for `day3 gears`.
+pub fn list(schematic: &Grid<char>, parts: &[EnginePart], symbols: &[(char, Point)], rule: &GearRule) {
+    for line in describe(schematic, parts, symbols, rule) {
+        println!("{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+    use crate::{index, Symbols};
+
+    #[test]
+    fn lists_the_example_gears() {
+        let schematic = Grid::from_lines(EXAMPLE, '.', |c| c);
+        let (parts, symbols) = index(&schematic, &Symbols::default());
+        let rule = GearRule::default();
+
+        let gears: Vec<(Point, Vec<usize>)> = candidates(&schematic, &parts, &symbols, &rule)
+            .iter()
+            .filter(|gear| rule.qualifies(gear))
+            .map(|gear| (gear.position, gear.parts.iter().map(|part| part.value).collect()))
+            .collect();
+        assert_eq!(gears, [(Point::new(1, 3), vec![467, 35]), (Point::new(8, 5), vec![755, 598])]);
+
+        assert_eq!(
+            describe(&schematic, &parts, &symbols, &rule),
+            [
+                "Gear * at line 2, column 4: 467, 35 -> 16345",
+                "Gear * at line 9, column 6: 755, 598 -> 451490",
+                "2 gears, ratios sum to 467835",
+                "Not a gear: * at line 5, column 4 touches 1 part",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(PartCount::parse("2"), Ok(PartCount::Exactly(2)));
+        assert_eq!(PartCount::parse("3+"), Ok(PartCount::AtLeast(3)));
+        assert_eq!(PartCount::parse("+"), Err("Expected a part count such as 2 or 2+, got +".to_string()));
+        assert_eq!(Combine::parse("sum"), Ok(Combine::Sum));
+        assert!(Combine::parse("max").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::ops::{Index, IndexMut, RangeInclusive};
+
+/// A cell position, counted from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Point {
+    pub fn new(row: usize, col: usize) -> Point {
+        Point { row, col }
+    }
+
+    /// The point `rows` down and `cols` right of this one, or `None` if that is above or left of
+    /// the grid.
+    fn offset(self, rows: isize, cols: isize) -> Option<Point> {
+        Some(Point { row: self.row.checked_add_signed(rows)?, col: self.col.checked_add_signed(cols)? })
+    }
+}
+
+/// Which cells count as touching a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Up, down, left and right.
+    #[cfg(test)]
+    Four,
+    /// The four above plus the diagonals.
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        #[cfg(test)]
+        const FOUR: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+        const EIGHT: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+        match self {
+            #[cfg(test)]
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+        }
+    }
+}
+
+/// A rectangle of cells stored row by row in one `Vec`, so every lookup is a multiplication and an
+/// addition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+        Grid { width, height, cells: vec![fill; width * height] }
+    }
+
+    /// Builds a grid from lines of text, converting every character with `convert`. Lines shorter
+    /// than the longest one are padded with `fill`.
+    pub fn from_lines(input: &str, fill: T, convert: impl Fn(char) -> T) -> Grid<T> {
+        let width = input.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut cells = Vec::new();
+
+        for line in input.lines() {
+            let start = cells.len();
+            cells.extend(line.chars().map(&convert));
+            cells.resize(start + width, fill.clone());
+        }
+
+        Grid { width, height: cells.len().checked_div(width).unwrap_or(0), cells }
+    }
+}
+
+impl Grid<bool> {
+    /// A grid where only `points` are set, such as where the symbols are.
+    pub fn mask(width: usize, height: usize, points: impl IntoIterator<Item = Point>) -> Grid<bool> {
+        let mut mask = Grid::new(width, height, false);
+        for point in points {
+            mask[point] = true;
+        }
+        mask
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.row < self.height && point.col < self.width
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.contains(point).then(|| &self.cells[point.row * self.width + point.col])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        if self.contains(point) {
+            Some(&mut self.cells[point.row * self.width + point.col])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    /// Every position in the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| Point::new(row, col)))
+    }
+
+    /// The cells touching `point` that lie inside the grid.
+    pub fn neighbours(&self, point: Point, connectivity: Connectivity) -> impl Iterator<Item = Point> + '_ {
+        connectivity
+            .offsets()
+            .iter()
+            .filter_map(move |&(rows, cols)| point.offset(rows, cols))
+            .filter(|&neighbour| self.contains(neighbour))
+    }
+
+    /// The ring of cells touching a horizontal run of `cols` on `row`, diagonals included, that lie
+    /// inside the grid. The run itself is left out.
+    pub fn around(&self, row: usize, cols: RangeInclusive<usize>) -> impl Iterator<Item = Point> + '_ {
+        let (first, last) = (*cols.start(), *cols.end());
+        let rows = row.saturating_sub(1)..=row + 1;
+        let cols = first.saturating_sub(1)..=last + 1;
+
+        rows.flat_map(move |r| cols.clone().map(move |c| Point::new(r, c)))
+            .filter(move |point| point.row != row || point.col < first || point.col > last)
+            .filter(|&point| self.contains(point))
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        self.get(point).unwrap_or_else(|| panic!("{:?} is outside the {}x{} grid", point, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(point).unwrap_or_else(|| panic!("{:?} is outside the {}x{} grid", point, width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(cells: &[(usize, usize)]) -> Vec<Point> {
+        cells.iter().map(|&(row, col)| Point::new(row, col)).collect()
+    }
+
+    fn sorted(points: impl Iterator<Item = Point>) -> Vec<Point> {
+        let mut points: Vec<Point> = points.collect();
+        points.sort();
+        points
+    }
+
+    #[test]
+    fn builds_from_lines() {
+        let grid = Grid::from_lines("ab\nc\ndef", '.', |c| c);
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.row(0), ['a', 'b', '.']);
+        assert_eq!(grid.row(1), ['c', '.', '.']);
+        assert_eq!(grid[Point::new(2, 2)], 'f');
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, 3)), None);
+
+        let empty = Grid::from_lines("", '.', |c| c);
+        assert_eq!((empty.width(), empty.height()), (0, 0));
+        assert_eq!(empty.points().count(), 0);
+    }
+
+    #[test]
+    fn masks_points() {
+        let mask = Grid::mask(3, 2, points(&[(0, 2), (1, 0), (0, 2)]));
+        assert_eq!(mask.row(0), [false, false, true]);
+        assert_eq!(mask.row(1), [true, false, false]);
+    }
+
+    #[test]
+    fn visits_points_row_by_row() {
+        let grid = Grid::new(2, 2, 0);
+        assert_eq!(grid.points().collect::<Vec<_>>(), points(&[(0, 0), (0, 1), (1, 0), (1, 1)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "is outside the 2x2 grid")]
+    fn panics_outside_the_grid() {
+        let mut grid = Grid::new(2, 2, 0);
+        grid[Point::new(0, 2)] = 1;
+    }
+
+    #[test]
+    fn neighbours_in_the_middle() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(sorted(grid.neighbours(Point::new(1, 1), Connectivity::Four)), points(&[(0, 1), (1, 0), (1, 2), (2, 1)]));
+        assert_eq!(grid.neighbours(Point::new(1, 1), Connectivity::Eight).count(), 8);
+    }
+
+    #[test]
+    fn neighbours_in_corners() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(sorted(grid.neighbours(Point::new(0, 0), Connectivity::Four)), points(&[(0, 1), (1, 0)]));
+        assert_eq!(sorted(grid.neighbours(Point::new(0, 0), Connectivity::Eight)), points(&[(0, 1), (1, 0), (1, 1)]));
+        assert_eq!(sorted(grid.neighbours(Point::new(2, 2), Connectivity::Eight)), points(&[(1, 1), (1, 2), (2, 1)]));
+    }
+
+    #[test]
+    fn neighbours_on_edges() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(sorted(grid.neighbours(Point::new(0, 1), Connectivity::Four)), points(&[(0, 0), (0, 2), (1, 1)]));
+        assert_eq!(
+            sorted(grid.neighbours(Point::new(1, 2), Connectivity::Eight)),
+            points(&[(0, 1), (0, 2), (1, 1), (2, 1), (2, 2)])
+        );
+
+        // A one-row grid has no cells above or below.
+        let line = Grid::new(3, 1, 0);
+        assert_eq!(sorted(line.neighbours(Point::new(0, 1), Connectivity::Eight)), points(&[(0, 0), (0, 2)]));
+    }
+
+    #[test]
+    fn around_a_run_in_the_middle() {
+        let grid = Grid::new(5, 3, 0);
+
+        assert_eq!(
+            sorted(grid.around(1, 1..=3)),
+            points(&[(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (1, 0), (1, 4), (2, 0), (2, 1), (2, 2), (2, 3), (2, 4)])
+        );
+    }
+
+    #[test]
+    fn around_a_run_at_the_top_left() {
+        let grid = Grid::new(4, 3, 0);
+
+        assert_eq!(sorted(grid.around(0, 0..=1)), points(&[(0, 2), (1, 0), (1, 1), (1, 2)]));
+        assert_eq!(sorted(grid.around(1, 0..=0)), points(&[(0, 0), (0, 1), (1, 1), (2, 0), (2, 1)]));
+    }
+
+    #[test]
+    fn around_a_run_at_the_bottom_right() {
+        let grid = Grid::new(4, 3, 0);
+
+        assert_eq!(sorted(grid.around(2, 2..=3)), points(&[(1, 1), (1, 2), (1, 3), (2, 1)]));
+        assert_eq!(grid.around(0, 0..=3).count(), 4);
+    }
+
+    #[test]
+    fn around_a_single_cell_matches_its_neighbours() {
+        let grid = Grid::new(4, 3, 0);
+
+        for point in grid.points() {
+            assert_eq!(sorted(grid.around(point.row, point.col..=point.col)), sorted(grid.neighbours(point, Connectivity::Eight)));
+        }
+    }
+}
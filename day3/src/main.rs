@@ -1,179 +1,352 @@
-use std::collections::{HashSet, HashMap};
+mod gears;
+mod grid;
+mod render;
+mod stream;
+mod symbols;
 
-#[derive(Debug)]
+use gears::{Combine, GearRule, PartCount};
+use grid::{Connectivity, Grid, Point};
+use symbols::Symbols;
+
+#[derive(Debug, PartialEq)]
 struct EnginePart {
-    id: String,
     line: usize,
     start: usize,
     end: usize,
     value: usize,
 }
 
-impl PartialEq for EnginePart {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+impl EnginePart {
+    /// Every cell touching any digit of the part, however long it is.
+    fn ring<'g, T>(&self, grid: &'g Grid<T>) -> impl Iterator<Item = Point> + 'g {
+        grid.around(self.line, self.start..=self.end)
+    }
+
+    /// The cells of the part's own digits.
+    fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        (self.start..=self.end).map(|col| Point::new(self.line, col))
     }
-}
 
-static SYMBOLS: [&'static str; 10] = ["*", "#", "/", "$", "+", "=", "-", "@", "&", "%"];
+    /// Whether `point` is in the part's ring, without needing a grid to walk it.
+    fn touches(&self, point: Point) -> bool {
+        let on_part = point.row == self.line && (self.start..=self.end).contains(&point.col);
+        point.row + 1 >= self.line && point.row <= self.line + 1 && point.col + 1 >= self.start && point.col <= self.end + 1 && !on_part
+    }
+}
 
-fn index_line(line: &str, line_number: usize) -> (Vec<EnginePart>, Vec<(char, usize)>) {
+fn index_line(schematic: &Grid<char>, line_number: usize, symbol_set: &Symbols) -> (Vec<EnginePart>, Vec<(char, usize)>) {
     let mut parts: Vec<EnginePart> = Vec::new();
     let mut symbols: Vec<(char, usize)> = Vec::new();
 
-    let mut start: isize = -1;
-    let mut end: usize = 0;
+    let mut start: Option<usize> = None;
+    let mut value: usize = 0;
 
-    for (i, c) in line.chars().enumerate() {
-        if c.is_numeric() {
-            if start == -1 {
-                start = i as isize;
-            }
-            end = i;
-
-        } else {
-            if start != -1 {
-                parts.push(EnginePart {
-                    id: format!("{}-{}", line_number, start).to_string(),
-                    line: line_number,
-                    start: start as usize,
-                    end,
-                    value: line[start as usize..end + 1].parse().unwrap(),
-                });
-                start = -1;
-                end = 0;
-            }
+    for (i, &c) in schematic.row(line_number).iter().enumerate() {
+        if let Some(digit) = c.to_digit(10) {
+            start.get_or_insert(i);
+            value = value * 10 + digit as usize;
+            continue;
+        }
 
-            if SYMBOLS.contains(&c.to_string().as_str()) {
-                symbols.push((c,i));
-            }
+        if let Some(start) = start.take() {
+            parts.push(EnginePart { line: line_number, start, end: i - 1, value });
+            value = 0;
+        }
+
+        if symbol_set.contains(c) {
+            symbols.push((c, i));
         }
     }
 
-    if start != -1 {
-        parts.push(EnginePart {
-            id: format!("{}-{}", line_number, start).to_string(),
-            line: line_number,
-            start: start as usize,
-            end,
-            value: line[start as usize..end + 1].parse().unwrap(),
-        });
+    if let Some(start) = start {
+        parts.push(EnginePart { line: line_number, start, end: schematic.width() - 1, value });
     }
 
     (parts, symbols)
 }
 
-fn is_adjacent(engine_part: &EnginePart, symbols: &HashSet<String>) -> bool {
-    let directions: [(i32, i32); 8] = [
-        (0, 1),
-        (1, 0),
-        (0, -1),
-        (-1, 0),
-        (1, 1),
-        (1, -1),
-        (-1, 1),
-        (-1, -1)
-    ];
-
-    for direction in directions.iter() {
-        let y = engine_part.line as i32 + direction.0;
-        let start_x = engine_part.start as i32 + direction.1;
-        let end_x = engine_part.end as i32 + direction.1;
-
-        if symbols.contains(&format!("{}-{}", y, start_x).to_string()) {
-            return true;
-        }
+fn is_adjacent(engine_part: &EnginePart, symbols: &Grid<bool>) -> bool {
+    engine_part.ring(symbols).any(|point| symbols[point])
+}
 
-        if symbols.contains(&format!("{}-{}", y, end_x).to_string()) {
-            return true;
+/// Adds the part to every gear touching any of its digits. Neighbouring digits share most of
+/// their neighbours, so a gear keeps the part only the first time it sees it.
+fn find_adjacent<'a>(engine_part: &'a EnginePart, gears: &mut Grid<Option<Vec<&'a EnginePart>>>) {
+    for cell in engine_part.cells() {
+        let neighbours: Vec<Point> = gears.neighbours(cell, Connectivity::Eight).collect();
+
+        for neighbour in neighbours {
+            if let Some(vec) = &mut gears[neighbour] {
+                if !vec.contains(&engine_part) {
+                    vec.push(engine_part);
+                }
+            }
         }
     }
+}
+
+/// Indexes every line of the schematic, returning all its parts and all its symbols.
+fn index(schematic: &Grid<char>, symbol_set: &Symbols) -> (Vec<EnginePart>, Vec<(char, Point)>) {
+    let mut parts: Vec<EnginePart> = Vec::new();
+    let mut symbols: Vec<(char, Point)> = Vec::new();
+
+    for i in 0..schematic.height() {
+        let (pts, syms) = index_line(schematic, i, symbol_set);
+
+        parts.extend(pts);
+        symbols.extend(syms.into_iter().map(|(c, col)| (c, Point::new(i, col))));
+    }
+
+    (parts, symbols)
+}
 
-    return false;
+/// Where the symbols are, for `is_adjacent` to look them up.
+fn symbol_mask(schematic: &Grid<char>, symbols: &[(char, Point)]) -> Grid<bool> {
+    Grid::mask(schematic.width(), schematic.height(), symbols.iter().map(|&(_, point)| point))
 }
 
-fn find_adjacent<'a>(engine_part: &'a EnginePart, gears: &mut HashMap<String, Vec<&'a EnginePart>>) {
-    let directions: [(i32, i32); 8] = [
-        (0, 1),
-        (1, 0),
-        (0, -1),
-        (-1, 0),
-        (1, 1),
-        (1, -1),
-        (-1, 1),
-        (-1, -1)
-    ];
-
-    for direction in directions.iter() {
-        let y = engine_part.line as i32 + direction.0;
-        let start_x = engine_part.start as i32 + direction.1;
-        let end_x = engine_part.end as i32 + direction.1;
-
-        if let Some(vec) = gears.get_mut(&format!("{}-{}", y, start_x).to_string()) {
-            if !vec.contains(&engine_part) {
-                vec.push(engine_part);
-            }
+fn sum_of_parts(schematic: &Grid<char>, symbol_set: &Symbols) -> usize {
+    let (parts, symbols) = index(schematic, symbol_set);
+    let symbols = symbol_mask(schematic, &symbols);
+
+    parts
+        .iter()
+        .filter(|p| is_adjacent(p, &symbols))
+        .map(|p| p.value)
+        .sum::<usize>()
+}
+
+fn part_one(schematic: &Grid<char>, symbol_set: &Symbols) {
+    println!("Part 1: {}", sum_of_parts(schematic, symbol_set));
+}
+
+fn sum_of_gear_ratios(schematic: &Grid<char>, symbol_set: &Symbols, rule: &GearRule) -> usize {
+    let (parts, symbols) = index(schematic, symbol_set);
+
+    gears::candidates(schematic, &parts, &symbols, rule)
+        .iter()
+        .filter(|gear| rule.qualifies(gear))
+        .map(|gear| rule.ratio(gear))
+        .sum::<usize>()
+}
+
+fn part_two(schematic: &Grid<char>, symbol_set: &Symbols, rule: &GearRule) {
+    println!("Part 2: {}", sum_of_gear_ratios(schematic, symbol_set, rule));
+}
+
+enum Mode {
+    Parts,
+    Symbols,
+    Gears,
+    Render(Vec<String>),
+    Stream(Vec<String>),
+    Generate(Vec<String>),
+}
+
+/// A symbol is any character other than a digit or the blank `.`. `--blank C` picks another
+/// blank, `--only CHARS` limits symbols to the given characters and `--exclude CHARS` leaves some
+/// out. `symbols` reports which symbols the schematic holds and how many parts touch each.
+///
+/// Gears are `*` symbols touching exactly two parts, worth their product, unless
+/// `--gear-symbols CHARS`, `--gear-parts N` (or `N+` for at least N) and `--combine product|sum`
+/// say otherwise. `gears` lists every gear with its position and parts, and
+/// `render [--format ansi|html|svg] [--output FILE]` draws the schematic with all of it highlighted.
+///
+/// `stream [FILE]` solves a schematic too big for memory, and `generate [--lines N] [--width N]`
+/// writes one to try it on.
+fn main() {
+    let input = include_str!("./input.txt");
+    let mut symbol_set = Symbols::default();
+    let mut rule = GearRule::default();
+    let mut mode = Mode::Parts;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--blank" => {
+                let blank = args.next().expect("--blank needs a character");
+                let mut chars = blank.chars();
+                symbol_set.blank = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => panic!("--blank needs a single character, got {}", blank),
+                };
+            },
+            "--only" => symbol_set.only = Some(args.next().expect("--only needs characters").chars().collect()),
+            "--exclude" => symbol_set.exclude = args.next().expect("--exclude needs characters").chars().collect(),
+            "--gear-symbols" => rule.symbols = args.next().expect("--gear-symbols needs characters").chars().collect(),
+            "--gear-parts" => {
+                let count = args.next().expect("--gear-parts needs a count such as 2 or 2+");
+                rule.parts = PartCount::parse(&count).unwrap_or_else(|e| panic!("{}", e));
+            },
+            "--combine" => {
+                let combine = args.next().expect("--combine needs product or sum");
+                rule.combine = Combine::parse(&combine).unwrap_or_else(|e| panic!("{}", e));
+            },
+            "symbols" => mode = Mode::Symbols,
+            "gears" => mode = Mode::Gears,
+            "render" => mode = Mode::Render(args.by_ref().collect()),
+            "stream" => mode = Mode::Stream(args.by_ref().collect()),
+            "generate" => mode = Mode::Generate(args.by_ref().collect()),
+            _ => panic!("Unknown argument {}", arg),
         }
-        
-        if let Some(vec) = gears.get_mut(&format!("{}-{}", y, end_x).to_string()) {
-            if !vec.contains(&engine_part) {
-                vec.push(engine_part);
+    }
+
+    let schematic = Grid::from_lines(input, symbol_set.blank, |c| c);
+
+    match mode {
+        Mode::Parts => {
+            part_one(&schematic, &symbol_set);
+            part_two(&schematic, &symbol_set, &rule);
+        },
+        Mode::Symbols => symbols::report(&schematic, &symbol_set),
+        Mode::Gears => {
+            let (parts, symbols) = index(&schematic, &symbol_set);
+            gears::list(&schematic, &parts, &symbols, &rule);
+        },
+        Mode::Render(args) => render::run(&schematic, &symbol_set, &rule, &args),
+        Mode::Stream(args) => {
+            if let Err(e) = stream::run(&symbol_set, &rule, &args) {
+                eprintln!("Could not stream the schematic: {}", e);
+                std::process::exit(1);
             }
-        }
+        },
+        Mode::Generate(args) => {
+            if let Err(e) = stream::generate(&args) {
+                eprintln!("Could not write the schematic: {}", e);
+                std::process::exit(1);
+            }
+        },
     }
 }
 
-fn part_one(input: &str) {
-    let mut symbols: HashSet<String> = HashSet::new();
-    let mut parts: Vec<EnginePart> = Vec::new();
-    
-    for (i, line) in input.lines().enumerate() {
-        let (pts, syms) = index_line(line, i);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        parts.extend(pts);
+    /// The schematic from the puzzle description.
+    pub const EXAMPLE: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
 
-        for symbol in syms {
-            symbols.insert(format!("{}-{}", i, symbol.1).to_string());
+    fn schematic(text: &str) -> Grid<char> {
+        Grid::from_lines(text, '.', |c| c)
+    }
+
+    /// Places a number of `len` digits in the middle of an empty schematic, then a symbol in turn on
+    /// every other cell, checking the number counts exactly when the symbol is in its ring.
+    fn check_every_ring_cell(len: usize) {
+        let number: String = (1..=len).map(|digit| char::from_digit(digit as u32 % 10, 10).unwrap()).collect();
+        let width = len + 4;
+
+        for row in 0..5 {
+            for col in 0..width {
+                if row == 2 && (2..2 + len).contains(&col) {
+                    continue;
+                }
+
+                let mut lines = vec![".".repeat(width); 5];
+                lines[2] = format!("..{}..", number);
+                lines[row].replace_range(col..col + 1, "#");
+
+                let touching = (1..=3).contains(&row) && (1..=len + 2).contains(&col);
+                let expected = if touching { number.parse().unwrap() } else { 0 };
+                let sum = sum_of_parts(&schematic(&lines.join("\n")), &Symbols::default());
+                assert_eq!(sum, expected, "{} digits, symbol at {},{}", len, row, col);
+            }
         }
     }
 
-    let sum = parts
-        .iter()
-        .filter(|p| is_adjacent(&p, &symbols))
-        .map(|p| p.value)
-        .sum::<usize>();
+    #[test]
+    fn one_digit_parts_touch_their_whole_ring() {
+        check_every_ring_cell(1);
+    }
 
-    println!("Part 1: {}", sum);
-}
+    #[test]
+    fn two_digit_parts_touch_their_whole_ring() {
+        check_every_ring_cell(2);
+    }
 
-fn part_two(input: &str) {
-    let mut gears: HashMap<String, Vec<&EnginePart>> = HashMap::new();
-    let mut parts: Vec<EnginePart> = Vec::new();
+    #[test]
+    fn three_digit_parts_touch_their_whole_ring() {
+        check_every_ring_cell(3);
+    }
 
-    for (i, line) in input.lines().enumerate() {
-        let (pts, syms) = index_line(line, i);
+    #[test]
+    fn longer_parts_touch_their_whole_ring() {
+        for len in 4..=8 {
+            check_every_ring_cell(len);
+        }
+    }
 
-        parts.extend(pts);
+    #[test]
+    fn touches_its_ring_only() {
+        let grid = Grid::new(7, 4, 0);
+        let parts = [
+            EnginePart { line: 0, start: 0, end: 1, value: 12 },
+            EnginePart { line: 2, start: 2, end: 4, value: 345 },
+            EnginePart { line: 3, start: 6, end: 6, value: 6 },
+        ];
 
-        for symbol in syms.iter().filter(|s| s.0 == '*') {
-            gears.insert(format!("{}-{}", i, symbol.1).to_string(), Vec::new());
+        for part in parts.iter() {
+            let mut ring: Vec<Point> = part.ring(&grid).collect();
+            ring.sort();
+            let touching: Vec<Point> = grid.points().filter(|&point| part.touches(point)).collect();
+            assert_eq!(touching, ring, "{:?}", part);
         }
     }
 
-    parts
-        .iter()
-        .for_each(|p| find_adjacent(&p, &mut gears));
+    #[test]
+    fn symbols_beside_middle_digits_only() {
+        assert_eq!(sum_of_parts(&schematic("...#...\n.12345.\n......."), &Symbols::default()), 12345);
+        assert_eq!(sum_of_parts(&schematic(".......\n.12345.\n....$.."), &Symbols::default()), 12345);
+        assert_eq!(sum_of_parts(&schematic("..@.....\n.123456.\n........\n.789012.\n....%..."), &Symbols::default()), 123456 + 789012);
+    }
+
+    #[test]
+    fn parts_on_the_edges() {
+        assert_eq!(sum_of_parts(&schematic("1234\n.+.."), &Symbols::default()), 1234);
+        assert_eq!(sum_of_parts(&schematic("..*.\n5678"), &Symbols::default()), 5678);
+    }
 
-    let sum = gears.iter()
-        .filter(|(_, v)| v.len() == 2)
-        .map(|(_, v)| v[0].value * v[1].value)
-        .sum::<usize>();
+    #[test]
+    fn any_character_but_digits_and_blanks_is_a_symbol() {
+        let text = "12.~..\n...34.\n5.....\n.Ω..6.\n....!7";
+        assert_eq!(sum_of_parts(&schematic(text), &Symbols::default()), 34 + 5 + 6 + 7);
 
-    println!("Part 2: {}", sum);
-}
+        let only = Symbols { only: Some(vec!['Ω', '!']), ..Symbols::default() };
+        assert_eq!(sum_of_parts(&schematic(text), &only), 5 + 6 + 7);
 
-fn main() {
-    let input = include_str!("./input.txt");
-    part_one(input);
-    part_two(input);
+        let exclude = Symbols { exclude: vec!['~'], ..Symbols::default() };
+        assert_eq!(sum_of_parts(&schematic(text), &exclude), 5 + 6 + 7);
+    }
+
+    #[test]
+    fn gears_beside_middle_digits_only() {
+        assert_eq!(sum_of_gear_ratios(&schematic(".12345.\n...*...\n.67890."), &Symbols::default(), &GearRule::default()), 12345 * 67890);
+        assert_eq!(sum_of_gear_ratios(&schematic("1000*2000"), &Symbols::default(), &GearRule::default()), 1000 * 2000);
+        // Three digits of 123 touch the gear, but it still only counts once.
+        assert_eq!(sum_of_gear_ratios(&schematic("123\n.*.\n..4"), &Symbols::default(), &GearRule::default()), 123 * 4);
+    }
+
+    #[test]
+    fn configurable_gear_rules() {
+        let text = "2.3.4\n.*#*.\n5...6";
+        let rule = |symbols: &str, parts: &str, combine: &str| GearRule {
+            symbols: symbols.chars().collect(),
+            parts: PartCount::parse(parts).unwrap(),
+            combine: Combine::parse(combine).unwrap(),
+        };
+
+        assert_eq!(sum_of_gear_ratios(&schematic(text), &Symbols::default(), &rule("*", "2", "product")), 0);
+        assert_eq!(sum_of_gear_ratios(&schematic(text), &Symbols::default(), &rule("*", "3", "product")), 2 * 3 * 5 + 3 * 4 * 6);
+        assert_eq!(sum_of_gear_ratios(&schematic(text), &Symbols::default(), &rule("*#", "3+", "sum")), 10 + 13);
+        assert_eq!(sum_of_gear_ratios(&schematic(text), &Symbols::default(), &rule("#", "1", "sum")), 3);
+    }
 }
@@ -0,0 +1,253 @@
+use crate::gears::{self, GearRule};
+use crate::grid::Grid;
+use crate::symbols::Symbols;
+use crate::{index, is_adjacent, symbol_mask};
+
+/// What a cell of the schematic turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    /// A digit of a part counted in part one.
+    Counted,
+    /// A digit of a number touching no symbol.
+    Excluded,
+    Symbol,
+    Gear,
+    /// A digit of a part attached to a gear.
+    GearPart,
+}
+
+impl Style {
+    const LEGEND: [Style; 5] = [Style::Counted, Style::Excluded, Style::Symbol, Style::Gear, Style::GearPart];
+
+    fn name(self) -> &'static str {
+        match self {
+            Style::Plain => "plain",
+            Style::Counted => "counted",
+            Style::Excluded => "excluded",
+            Style::Symbol => "symbol",
+            Style::Gear => "gear",
+            Style::GearPart => "gear-part",
+        }
+    }
+
+    fn ansi(self) -> &'static str {
+        match self {
+            Style::Plain => "2",
+            Style::Counted => "32",
+            Style::Excluded => "31",
+            Style::Symbol => "35",
+            Style::Gear => "1;33",
+            Style::GearPart => "1;36",
+        }
+    }
+
+    fn colour(self) -> &'static str {
+        match self {
+            Style::Plain => "#9e9e9e",
+            Style::Counted => "#2e7d32",
+            Style::Excluded => "#c62828",
+            Style::Symbol => "#6a1b9a",
+            Style::Gear => "#f9a825",
+            Style::GearPart => "#00838f",
+        }
+    }
+
+    fn bold(self) -> bool {
+        matches!(self, Style::Gear | Style::GearPart)
+    }
+}
+
+/// Works out the style of every cell, using the same adjacency and gear rules as the answers.
+fn styles(schematic: &Grid<char>, symbol_set: &Symbols, rule: &GearRule) -> Grid<Style> {
+    let (parts, symbols) = index(schematic, symbol_set);
+    let mut styles = Grid::new(schematic.width(), schematic.height(), Style::Plain);
+    let mask = symbol_mask(schematic, &symbols);
+
+    for &(_, point) in symbols.iter() {
+        styles[point] = Style::Symbol;
+    }
+
+    for part in parts.iter() {
+        let style = if is_adjacent(part, &mask) { Style::Counted } else { Style::Excluded };
+        for point in part.cells() {
+            styles[point] = style;
+        }
+    }
+
+    for gear in gears::candidates(schematic, &parts, &symbols, rule).iter().filter(|gear| rule.qualifies(gear)) {
+        styles[gear.position] = Style::Gear;
+        for part in gear.parts.iter() {
+            for point in part.cells() {
+                styles[point] = Style::GearPart;
+            }
+        }
+    }
+
+    styles
+}
+
+/// Splits a row into runs of cells with the same style.
+fn runs(text: &[char], styles: &[Style]) -> Vec<(Style, String)> {
+    let mut runs: Vec<(Style, String)> = Vec::new();
+
+    for (&c, &style) in text.iter().zip(styles) {
+        match runs.last_mut() {
+            Some((last, run)) if *last == style => run.push(c),
+            _ => runs.push((style, c.to_string())),
+        }
+    }
+
+    runs
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn ansi(schematic: &Grid<char>, styles: &Grid<Style>) -> String {
+    let mut out = String::new();
+
+    for row in 0..schematic.height() {
+        for (style, run) in runs(schematic.row(row), styles.row(row)) {
+            out.push_str(&format!("\x1b[{}m{}\x1b[0m", style.ansi(), run));
+        }
+        out.push('\n');
+    }
+
+    let legend: Vec<String> = Style::LEGEND.iter().map(|style| format!("\x1b[{}m{}\x1b[0m", style.ansi(), style.name())).collect();
+    out.push_str(&legend.join("  "));
+    out.push('\n');
+    out
+}
+
+fn html(schematic: &Grid<char>, styles: &Grid<Style>) -> String {
+    let mut css = String::new();
+    for style in [Style::Plain].iter().chain(Style::LEGEND.iter()) {
+        let weight = if style.bold() { "bold" } else { "normal" };
+        css.push_str(&format!(".{} {{ color: {}; font-weight: {}; }}\n", style.name(), style.colour(), weight));
+    }
+
+    let mut body = String::new();
+    for row in 0..schematic.height() {
+        for (style, run) in runs(schematic.row(row), styles.row(row)) {
+            body.push_str(&format!("<span class=\"{}\">{}</span>", style.name(), escape(&run)));
+        }
+        body.push('\n');
+    }
+
+    let legend: Vec<String> = Style::LEGEND.iter().map(|style| format!("<span class=\"{0}\">{0}</span>", style.name())).collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Engine schematic</title>\n<style>\n{}</style>\n</head>\n<body>\n<pre>\n{}</pre>\n<p>{}</p>\n</body>\n</html>\n",
+        css,
+        body,
+        legend.join(" ")
+    )
+}
+
+fn svg(schematic: &Grid<char>, styles: &Grid<Style>) -> String {
+    // Cell size in pixels for a 14px monospace font. The legend needs about fifty cells of width.
+    const WIDTH: usize = 9;
+    const HEIGHT: usize = 16;
+
+    let mut rows = String::new();
+    for row in 0..schematic.height() {
+        let mut line = String::new();
+        for (style, run) in runs(schematic.row(row), styles.row(row)) {
+            let weight = if style.bold() { " font-weight=\"bold\"" } else { "" };
+            line.push_str(&format!("<tspan fill=\"{}\"{}>{}</tspan>", style.colour(), weight, escape(&run)));
+        }
+        rows.push_str(&format!("<text x=\"0\" y=\"{}\">{}</text>\n", (row + 1) * HEIGHT, line));
+    }
+
+    let legend: Vec<String> = Style::LEGEND.iter().map(|style| format!("<tspan fill=\"{}\">{}</tspan>", style.colour(), style.name())).collect();
+    rows.push_str(&format!("<text x=\"0\" y=\"{}\">{}</text>\n", (schematic.height() + 2) * HEIGHT, legend.join(" ")));
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"14\" xml:space=\"preserve\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{}</svg>\n",
+        (schematic.width() * WIDTH).max(50 * WIDTH),
+        (schematic.height() + 2) * HEIGHT + HEIGHT / 2,
+        rows
+    )
+}
+
+/// Draws the schematic with counted parts, excluded numbers, symbols, gears and gear parts picked
+/// out in colour.
+pub fn run(schematic: &Grid<char>, symbol_set: &Symbols, rule: &GearRule, args: &[String]) {
+    let mut format = "ansi".to_string();
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().expect("--format needs ansi, html or svg").to_string(),
+            "--output" => output = Some(args.next().expect("--output needs a file name")),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let styles = styles(schematic, symbol_set, rule);
+    let rendered = match format.as_str() {
+        "ansi" => ansi(schematic, &styles),
+        "html" => html(schematic, &styles),
+        "svg" => svg(schematic, &styles),
+        _ => panic!("Unknown format {}, expected ansi, html or svg", format),
+    };
+
+    match output {
+        Some(file) => {
+            if let Err(e) = std::fs::write(file, rendered) {
+                eprintln!("Could not write {}: {}", file, e);
+                std::process::exit(1);
+            }
+        },
+        None => print!("{}", rendered),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letter(style: &Style) -> char {
+        match style {
+            Style::Plain => '.',
+            Style::Counted => 'c',
+            Style::Excluded => 'x',
+            Style::Symbol => 's',
+            Style::Gear => 'g',
+            Style::GearPart => 'p',
+        }
+    }
+
+    /// Classifies every cell of `text`, one letter per cell.
+    fn classify(text: &str, rule: &GearRule) -> Vec<String> {
+        let schematic = Grid::from_lines(text, '.', |c| c);
+        let styles = styles(&schematic, &Symbols::default(), rule);
+        (0..styles.height()).map(|row| styles.row(row).iter().map(letter).collect()).collect()
+    }
+
+    #[test]
+    fn classifies_cells() {
+        assert_eq!(classify("12....8\n..*.9..\n.3...#4", &GearRule::default()), ["pp....x", "..g.c..", ".p...sc"]);
+    }
+
+    #[test]
+    fn gear_symbols_without_the_right_parts_are_plain_symbols() {
+        assert_eq!(classify("1.2\n.*.\n..3", &GearRule::default()), ["c.c", ".s.", "..c"]);
+        assert_eq!(
+            classify("1.2\n.*.\n..3", &GearRule { parts: gears::PartCount::Exactly(3), ..GearRule::default() }),
+            ["p.p", ".g.", "..p"]
+        );
+    }
+
+    #[test]
+    fn splits_rows_into_runs() {
+        let text: Vec<char> = "12.*".chars().collect();
+        let styles = [Style::Counted, Style::Counted, Style::Plain, Style::Symbol];
+
+        assert_eq!(runs(&text, &styles), [(Style::Counted, "12".to_string()), (Style::Plain, ".".to_string()), (Style::Symbol, "*".to_string())]);
+        assert_eq!(escape("<&>"), "&lt;&amp;&gt;");
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::gears::{Gear, GearRule};
+use crate::grid::{Grid, Point};
+use crate::symbols::Symbols;
+use crate::{index_line, EnginePart};
+
+#[derive(Debug, Default)]
+struct Totals {
+    lines: usize,
+    parts: usize,
+    gears: usize,
+    gear_ratios: usize,
+}
+
+/// The parts and symbols of one line, found once when the line is read.
+struct Row {
+    parts: Vec<EnginePart>,
+    symbols: Vec<(char, Point)>,
+}
+
+impl Row {
+    fn index(line: &str, line_number: usize, symbol_set: &Symbols) -> Row {
+        let schematic = Grid::from_lines(line, symbol_set.blank, |c| c);
+        let (mut parts, symbols) = index_line(&schematic, 0, symbol_set);
+
+        for part in parts.iter_mut() {
+            part.line = line_number;
+        }
+
+        Row { parts, symbols: symbols.into_iter().map(|(c, col)| (c, Point::new(line_number, col))).collect() }
+    }
+}
+
+/// Solves the row in the middle of a window of up to three rows. Parts and gears never reach
+/// further than one row up or down, so the rows around it are all it needs to be solved exactly
+/// as it would be in the whole schematic.
+fn solve_middle(window: &VecDeque<Row>, middle: usize, rule: &GearRule, totals: &mut Totals) {
+    let near_symbol = |part: &EnginePart| window.iter().flat_map(|row| row.symbols.iter()).any(|&(_, point)| part.touches(point));
+
+    totals.parts += window[middle]
+        .parts
+        .iter()
+        .filter(|part| near_symbol(part))
+        .map(|part| part.value)
+        .sum::<usize>();
+
+    for &(symbol, position) in window[middle].symbols.iter().filter(|(c, _)| rule.symbols.contains(c)) {
+        let parts = window.iter().flat_map(|row| row.parts.iter()).filter(|part| part.touches(position)).collect();
+        let gear = Gear { position, symbol, parts };
+
+        if rule.qualifies(&gear) {
+            totals.gears += 1;
+            totals.gear_ratios += rule.ratio(&gear);
+        }
+    }
+}
+
+/// Reads the schematic one line at a time, keeping only the three rows around the one being
+/// solved, so memory stays the same however many lines there are.
+fn sum_reader<R: BufRead>(reader: R, symbol_set: &Symbols, rule: &GearRule) -> io::Result<Totals> {
+    let mut totals = Totals::default();
+    let mut window: VecDeque<Row> = VecDeque::with_capacity(3);
+
+    for line in reader.lines() {
+        window.push_back(Row::index(&line?, totals.lines, symbol_set));
+        totals.lines += 1;
+
+        match window.len() {
+            // The first row has no row above it.
+            2 => solve_middle(&window, 0, rule, &mut totals),
+            3 => {
+                solve_middle(&window, 1, rule, &mut totals);
+                window.pop_front();
+            },
+            _ => {},
+        }
+    }
+
+    // The last row has no row below it.
+    if !window.is_empty() {
+        let middle = window.len() - 1;
+        solve_middle(&window, middle, rule, &mut totals);
+    }
+
+    Ok(totals)
+}
+
+/// Solves both parts for a schematic of any size read from `FILE`, or from stdin without one.
+pub fn run(symbol_set: &Symbols, rule: &GearRule, args: &[String]) -> io::Result<()> {
+    let totals = match args {
+        [] => sum_reader(io::stdin().lock(), symbol_set, rule)?,
+        [file] => sum_reader(BufReader::with_capacity(1 << 20, File::open(file)?), symbol_set, rule)?,
+        _ => panic!("stream takes at most one file name, got {}", args.join(" ")),
+    };
+
+    println!("Part 1: {}", totals.parts);
+    println!("Part 2: {} ({} gears in {} lines)", totals.gear_ratios, totals.gears, totals.lines);
+
+    Ok(())
+}
+
+/// A small linear congruential generator starting from `seed`, returning numbers below the bound
+/// it is called with. Plenty for making up schematics, and the same seed always gives the same one.
+fn random(seed: u64) -> impl FnMut(usize) -> usize {
+    let mut state = seed;
+    move |bound| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) % bound as u64) as usize
+    }
+}
+
+/// Writes a random schematic to stdout for trying out `stream`. Every run starts from the same
+/// seed, so a given size always gives the same schematic and the same totals to check against.
+pub fn generate(args: &[String]) -> io::Result<()> {
+    const SYMBOLS: &[u8] = b"*#/$+=-@&%";
+
+    let mut lines = 1_000_000;
+    let mut width = 140;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lines" => lines = args.next().and_then(|n| n.parse().ok()).expect("--lines needs a number"),
+            "--width" => width = args.next().and_then(|n| n.parse().ok()).expect("--width needs a number"),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let mut next = random(2023);
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut line = Vec::with_capacity(width + 1);
+    for _ in 0..lines {
+        line.clear();
+
+        while line.len() < width {
+            match next(20) {
+                // Numbers of one to three digits, kept apart so they never run together.
+                0..=2 => {
+                    line.push(b'1' + next(9) as u8);
+                    line.extend((0..next(3)).map(|_| b'0' + next(10) as u8));
+                    line.push(b'.');
+                },
+                3 => line.push(SYMBOLS[next(SYMBOLS.len())]),
+                _ => line.push(b'.'),
+            }
+        }
+
+        line.truncate(width);
+        line.push(b'\n');
+        out.write_all(&line)?;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gears::{Combine, PartCount};
+    use crate::tests::EXAMPLE;
+    use crate::{sum_of_gear_ratios, sum_of_parts};
+
+    /// Checks that streaming `text` gives the same totals as solving it whole.
+    fn assert_agrees(text: &str, rule: &GearRule) -> Totals {
+        let symbol_set = Symbols::default();
+        let schematic = Grid::from_lines(text, symbol_set.blank, |c| c);
+        let totals = sum_reader(text.as_bytes(), &symbol_set, rule).unwrap();
+
+        assert_eq!(totals.parts, sum_of_parts(&schematic, &symbol_set), "parts of {:?}", text);
+        assert_eq!(totals.gear_ratios, sum_of_gear_ratios(&schematic, &symbol_set, rule), "gear ratios of {:?}", text);
+        assert_eq!(totals.lines, text.lines().count());
+        totals
+    }
+
+    #[test]
+    fn streams_the_example() {
+        let totals = assert_agrees(EXAMPLE, &GearRule::default());
+        assert_eq!((totals.parts, totals.gears, totals.gear_ratios), (4361, 2, 467835));
+    }
+
+    #[test]
+    fn streams_one_and_two_lines() {
+        assert_eq!(assert_agrees("12*34..5", &GearRule::default()).gear_ratios, 12 * 34);
+        assert_eq!(assert_agrees("12.*.\n...56", &GearRule::default()).parts, 56);
+        assert_eq!(assert_agrees("...7\n12*.", &GearRule::default()).gear_ratios, 12 * 7);
+        assert_eq!(assert_agrees("", &GearRule::default()).lines, 0);
+    }
+
+    #[test]
+    fn streams_gears_with_parts_above_and_below() {
+        let totals = assert_agrees("....\n.12.\n..*.\n.34.\n....", &GearRule::default());
+        assert_eq!((totals.parts, totals.gears, totals.gear_ratios), (46, 1, 12 * 34));
+
+        // Four parts touching the gear only at its corners.
+        assert_eq!(assert_agrees("5.6\n.*.\n7.8", &GearRule { parts: PartCount::AtLeast(2), ..GearRule::default() }).gear_ratios, 5 * 6 * 7 * 8);
+    }
+
+    #[test]
+    fn streams_lines_of_different_lengths() {
+        assert_eq!(assert_agrees("1\n.*\n..23", &GearRule::default()).gear_ratios, 23);
+        assert_eq!(assert_agrees("..\n\n12\n#", &GearRule::default()).parts, 12);
+    }
+
+    #[test]
+    fn streams_other_gear_rules() {
+        let rule = GearRule { symbols: vec!['*', '#'], parts: PartCount::AtLeast(1), combine: Combine::Sum };
+        assert_agrees(EXAMPLE, &rule);
+        assert_agrees("2.3.4\n.*#*.\n5...6", &rule);
+    }
+
+    #[test]
+    fn streams_a_generated_schematic() {
+        // Denser than `generate` writes, so gears with several parts turn up, and small enough to
+        // solve whole.
+        let mut next = random(7);
+        let text: Vec<String> = (0..40)
+            .map(|_| (0..30).map(|_| match next(6) { 0 => '*', 1 => '#', 2..=3 => char::from(b'0' + next(10) as u8), _ => '.' }).collect())
+            .collect();
+
+        assert_agrees(&text.join("\n"), &GearRule { parts: PartCount::AtLeast(2), ..GearRule::default() });
+        assert_agrees(&text.join("\n"), &GearRule::default());
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use crate::grid::Grid;
+use crate::{index, is_adjacent, symbol_mask};
+
+/// Which characters of the schematic count as symbols: anything that is neither a digit nor the
+/// blank character, narrowed down by an optional include list and an exclude list.
+#[derive(Debug, Clone)]
+pub struct Symbols {
+    pub blank: char,
+    /// When set, only these characters are symbols.
+    pub only: Option<Vec<char>>,
+    pub exclude: Vec<char>,
+}
+
+impl Default for Symbols {
+    fn default() -> Symbols {
+        Symbols { blank: '.', only: None, exclude: Vec::new() }
+    }
+}
+
+impl Symbols {
+    /// Whether `c` could be a symbol at all, before the include and exclude lists apply.
+    fn is_candidate(&self, c: char) -> bool {
+        !c.is_ascii_digit() && c != self.blank
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.is_candidate(c)
+            && self.only.as_ref().is_none_or(|only| only.contains(&c))
+            && !self.exclude.contains(&c)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Usage {
+    occurrences: usize,
+    parts: usize,
+    /// The values of the parts touching the symbol, added up.
+    sum: usize,
+}
+
+/// What `report` prints: the usage of every symbol character, and how often each character the
+/// configuration leaves out occurs.
+#[derive(Debug, Default)]
+struct Tally {
+    usage: BTreeMap<char, Usage>,
+    ignored: BTreeMap<char, usize>,
+}
+
+fn tally(schematic: &Grid<char>, symbols: &Symbols) -> Tally {
+    let mut tally = Tally::default();
+    let (parts, syms) = index(schematic, symbols);
+    let mask = symbol_mask(schematic, &syms);
+
+    for &(c, _) in syms.iter() {
+        tally.usage.entry(c).or_default().occurrences += 1;
+    }
+
+    for point in schematic.points() {
+        let c = schematic[point];
+        if symbols.is_candidate(c) && !symbols.contains(c) {
+            *tally.ignored.entry(c).or_default() += 1;
+        }
+    }
+
+    // A part touching the same symbol character twice still counts once for it.
+    for part in parts.iter().filter(|part| is_adjacent(part, &mask)) {
+        let mut touched: Vec<char> = part.ring(&mask).filter(|&point| mask[point]).map(|point| schematic[point]).collect();
+        touched.sort();
+        touched.dedup();
+
+        for c in touched {
+            let usage = tally.usage.entry(c).or_default();
+            usage.parts += 1;
+            usage.sum += part.value;
+        }
+    }
+
+    tally
+}
+
+/// Lists every symbol character in the schematic, how often it occurs and which parts touch it,
+/// along with the characters left out by the configuration.
+pub fn report(schematic: &Grid<char>, symbols: &Symbols) {
+    let tally = tally(schematic, symbols);
+
+    println!("Symbol  Count  Parts touched  Part sum");
+    for (c, usage) in tally.usage.iter() {
+        println!("{:<6}  {:>5}  {:>13}  {:>8}", c, usage.occurrences, usage.parts, usage.sum);
+    }
+
+    if tally.ignored.is_empty() {
+        println!("No characters ignored");
+    } else {
+        let ignored: Vec<String> = tally.ignored.iter().map(|(c, count)| format!("{} ({})", c, count)).collect();
+        println!("Ignored: {}", ignored.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+
+    fn usage(occurrences: usize, parts: usize, sum: usize) -> Usage {
+        Usage { occurrences, parts, sum }
+    }
+
+    #[test]
+    fn tallies_the_example() {
+        let tally = tally(&Grid::from_lines(EXAMPLE, '.', |c| c), &Symbols::default());
+
+        let expected = BTreeMap::from([
+            ('#', usage(1, 1, 633)),
+            ('$', usage(1, 1, 664)),
+            ('*', usage(3, 5, 467 + 35 + 617 + 755 + 598)),
+            ('+', usage(1, 1, 592)),
+        ]);
+        assert_eq!(tally.usage, expected);
+        assert!(tally.ignored.is_empty());
+
+        // Every counted part touches exactly one symbol, so the sums add up to part one.
+        assert_eq!(tally.usage.values().map(|usage| usage.sum).sum::<usize>(), 4361);
+    }
+
+    #[test]
+    fn tallies_ignored_characters() {
+        let symbols = Symbols { exclude: vec!['*'], ..Symbols::default() };
+        let tally = tally(&Grid::from_lines(EXAMPLE, '.', |c| c), &symbols);
+
+        assert_eq!(tally.usage.get(&'*'), None);
+        assert_eq!(tally.ignored, BTreeMap::from([('*', 3)]));
+        assert_eq!(tally.usage[&'#'], usage(1, 1, 633));
+    }
+}
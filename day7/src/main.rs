@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+mod stats;
 
 #[derive(Debug)]
 #[derive(Clone)]
@@ -8,9 +9,9 @@ enum Hand {
     OnePair { value: char, hand: String },
     TwoPair { first: char, second: char, hand: String },
     ThreeOfAKind { value: char, hand: String },
-    FullHouse { three: char, two: char, hand: String},
+    FullHouse { hand: String },
     FourOfAKind { value: char, hand: String },
-    FiveOfAKind { value: char, hand: String },
+    FiveOfAKind { hand: String },
     Empty { hand: String },
 }
 
@@ -35,9 +36,9 @@ impl Hand {
             Hand::OnePair { hand, .. } => hand.to_string(),
             Hand::TwoPair { hand, .. } => hand.to_string(),
             Hand::ThreeOfAKind { hand, .. } => hand.to_string(),
-            Hand::FullHouse { hand, .. } => hand.to_string(),
+            Hand::FullHouse { hand } => hand.to_string(),
             Hand::FourOfAKind { hand, .. } => hand.to_string(),
-            Hand::FiveOfAKind { hand, .. } => hand.to_string(),
+            Hand::FiveOfAKind { hand } => hand.to_string(),
         }
     }
 
@@ -46,7 +47,7 @@ impl Hand {
 
         match self {
             Hand::Empty { .. } => {
-                return Hand::FiveOfAKind { value: 'A', hand: "JJJJJ".to_string() }
+                return Hand::FiveOfAKind { hand: "JJJJJ".to_string() }
             },
             Hand::HighCard { value , .. } => {
                 if wildcards == 1 {
@@ -56,7 +57,7 @@ impl Hand {
                 } else if wildcards == 3 {
                     return Hand::FourOfAKind {  value: *value, hand: self.hand_str() }
                 } else if wildcards == 4 {
-                    return Hand::FiveOfAKind { value: *value, hand: self.hand_str() }
+                    return Hand::FiveOfAKind { hand: self.hand_str() }
                 }
             },
             Hand::OnePair { value, .. } => {
@@ -65,44 +66,78 @@ impl Hand {
                 } else if wildcards == 2 {
                     return Hand::FourOfAKind { value: *value, hand: self.hand_str() }
                 } else if wildcards == 3 {
-                    return Hand::FiveOfAKind { value: *value, hand: self.hand_str() }
+                    return Hand::FiveOfAKind { hand: self.hand_str() }
                 }
             },
-            Hand::TwoPair { first, second, .. } => {
-                if wildcards == 1 {
-                    return Hand::FullHouse { three: *first, two: *second, hand: self.hand_str() }
-                }
+            Hand::TwoPair { .. } if wildcards == 1 => {
+                return Hand::FullHouse { hand: self.hand_str() }
             },
             Hand::ThreeOfAKind { value , ..} => {
                 if wildcards == 1 {
                     return Hand::FourOfAKind { value: *value, hand: self.hand_str() }
                 } else if wildcards == 2 {
-                    return Hand::FiveOfAKind { value: *value, hand: self.hand_str() }
+                    return Hand::FiveOfAKind { hand: self.hand_str() }
                 }
             },
-            Hand::FourOfAKind { value , ..} => {
-                if wildcards == 1 {
-                    return Hand::FiveOfAKind { value: *value, hand: self.hand_str() }
-                }
+            Hand::FourOfAKind { .. } if wildcards == 1 => {
+                return Hand::FiveOfAKind { hand: self.hand_str() }
             },
             _ => {},
         }
-        return self.clone();
+        self.clone()
     }
 }
 
+/// Category names indexed by `Hand::score`.
+const CATEGORY_NAMES: [&str; 8] = [
+    "Empty",
+    "High card",
+    "One pair",
+    "Two pair",
+    "Three of a kind",
+    "Full house",
+    "Four of a kind",
+    "Five of a kind",
+];
+
 const CARD_VALUE: &[char] = &['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'];
 const CARD_VALUE_PART_TWO: &[char] = &['A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J'];
 
+/// The two rule sets of the puzzle: part one plays `J` as a jack, part two as a joker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rules {
+    Standard,
+    Jokers,
+}
+
+impl Rules {
+    fn card_values(&self) -> &'static [char] {
+        match self {
+            Rules::Standard => CARD_VALUE,
+            Rules::Jokers => CARD_VALUE_PART_TWO,
+        }
+    }
+
+    fn classify(&self, hand: String) -> Hand {
+        match self {
+            Rules::Standard => parse_hand(hand, CARD_VALUE),
+            Rules::Jokers => parse_hand(hand, CARD_VALUE_PART_TWO).boost_with_wildcards(),
+        }
+    }
+}
+
 fn parse_hand(hand: String, card_values: &[char]) -> Hand {
     let mut char_vector: Vec<char> = hand.chars().collect();
     char_vector
         .sort_by(|a, b| card_values.iter().position(|&x| x == *a)
         .cmp(&card_values.iter().position(|&y| y == *b)));
 
-    return char_vector.iter().enumerate().fold(Hand::Empty { hand }, |current_hand, (i, next_card)| {
+    // Only the joker rules rank `J` last. There it is a wildcard, left for `boost_with_wildcards`.
+    let jokers = card_values.last() == Some(&'J');
+
+    char_vector.iter().enumerate().fold(Hand::Empty { hand }, |current_hand, (i, next_card)| {
 
-        if next_card == &'J' {
+        if jokers && next_card == &'J' {
             return current_hand;
         }
 
@@ -117,7 +152,7 @@ fn parse_hand(hand: String, card_values: &[char]) -> Hand {
                         return Hand::OnePair { value: *next_card, hand: hand.to_string() }
                     }
                 }
-                return current_hand;
+                current_hand
             },
             Hand::OnePair { value , ref hand} => {
                 if value == *next_card {
@@ -128,15 +163,13 @@ fn parse_hand(hand: String, card_values: &[char]) -> Hand {
                     }
                 }
 
-                return current_hand;
+                current_hand
             },
             Hand::TwoPair { first, second , ref hand} => {
-                if first == *next_card {
-                    return Hand::FullHouse { three: first, two: second, hand: hand.to_string() }
-                } else if second == *next_card {
-                    return Hand::FullHouse { three: second, two: first, hand: hand.to_string() }
+                if first == *next_card || second == *next_card {
+                    Hand::FullHouse { hand: hand.to_string() }
                 } else {
-                    return current_hand;
+                    current_hand
                 }
             },
             Hand::ThreeOfAKind { value , ref hand} => {
@@ -144,29 +177,29 @@ fn parse_hand(hand: String, card_values: &[char]) -> Hand {
                     return Hand::FourOfAKind { value, hand: hand.to_string() }
                 } else if let Some(previous_value) = char_vector.get(i - 1) {
                     if previous_value == next_card {
-                        return Hand::FullHouse { three: value, two: *next_card, hand: hand.to_string() }
+                        return Hand::FullHouse { hand: hand.to_string() }
                     }
                 }
-                return current_hand;
+                current_hand
             },
             Hand::FullHouse { .. } => {
-                return current_hand;
+                current_hand
             },
             Hand::FourOfAKind { value , ref hand} => {
                 if value == *next_card {
-                    return Hand::FiveOfAKind { value , hand: hand.to_string() }
+                    Hand::FiveOfAKind { hand: hand.to_string() }
                 } else {
-                    return current_hand;
+                    current_hand
                 }
             },
             Hand::FiveOfAKind { .. } => {
-                return current_hand;
+                current_hand
             },
         }
-    });
+    })
 }
 
-fn part_one(input: &str) {
+fn part_one(input: &str) -> usize {
     let mut hands = input
         .lines()
         .map(|line| {
@@ -174,7 +207,7 @@ fn part_one(input: &str) {
             let hand_string = parts.next().unwrap().to_string();
             let bet = parts.next().unwrap().parse::<usize>().unwrap();
             let hand = parse_hand(hand_string, CARD_VALUE);
-            return (hand, bet);
+            (hand, bet)
         })
         .collect::<Vec<(Hand, usize)>>();
 
@@ -183,7 +216,7 @@ fn part_one(input: &str) {
             let hand_b = &b.0;
 
             if hand_a.score() != hand_b.score() {
-                return hand_a.score().cmp(&hand_b.score());
+                hand_a.score().cmp(&hand_b.score())
             } else {
                 let hand_a_str = hand_a.hand_str();
                 let hand_b_str = hand_b.hand_str();
@@ -201,15 +234,16 @@ fn part_one(input: &str) {
                     index += 1;
                 }
 
-                return Ordering::Equal;
+                Ordering::Equal
             }
         });
 
     let sum = hands.iter().enumerate().fold(0, |acc, step| {
-        return acc + (step.0 + 1 ) * step.1.1
+        acc + (step.0 + 1 ) * step.1.1
     });
 
     println!("Part one: {}", sum);
+    sum
 }
 
 fn part_two(input: &str) {
@@ -220,7 +254,7 @@ fn part_two(input: &str) {
             let hand_string = parts.next().unwrap().to_string();
             let bet = parts.next().unwrap().parse::<usize>().unwrap();
             let hand = parse_hand(hand_string, CARD_VALUE_PART_TWO).boost_with_wildcards();
-            return (hand, bet);
+            (hand, bet)
         })
         .collect::<Vec<(Hand, usize)>>();
 
@@ -229,7 +263,7 @@ fn part_two(input: &str) {
             let hand_b = &b.0;
 
             if hand_a.score() != hand_b.score() {
-                return hand_a.score().cmp(&hand_b.score());
+                hand_a.score().cmp(&hand_b.score())
             } else {
                 let hand_a_str = hand_a.hand_str();
                 let hand_b_str = hand_b.hand_str();
@@ -247,12 +281,12 @@ fn part_two(input: &str) {
                     index += 1;
                 }
 
-                return Ordering::Equal;
+                Ordering::Equal
             }
         });
 
     let sum = hands.iter().enumerate().fold(0, |acc, step| {
-        return acc + (step.0 + 1 ) * step.1.1
+        acc + (step.0 + 1 ) * step.1.1
     });
 
     println!("Part one: {}", sum);
@@ -260,6 +294,35 @@ fn part_two(input: &str) {
 
 fn main() {
     let input = include_str!("input.txt");
-    part_one(input);
-    part_two(input);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("stats") => stats::run(input, &args[1..]),
+        _ => {
+            part_one(input);
+            part_two(input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_one_plays_jacks_as_jacks() {
+        assert_eq!(part_one("32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483"), 6440);
+
+        // JJ234 is a pair of jacks. Skipping the jacks made it a high card and ranked it below 22345.
+        assert_eq!(part_one("JJ234 1\n22345 2"), 4);
+    }
+
+    #[test]
+    fn jacks_are_only_wild_under_joker_rules() {
+        let standard = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA", "JJJJJ"].map(|hand| Rules::Standard.classify(hand.to_string()).score());
+        assert_eq!(standard, [2, 4, 3, 3, 4, 7]);
+
+        let jokers = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA", "JJJJJ"].map(|hand| Rules::Jokers.classify(hand.to_string()).score());
+        assert_eq!(jokers, [2, 6, 3, 6, 6, 7]);
+    }
 }
@@ -0,0 +1,226 @@
+use crate::{Rules, CATEGORY_NAMES};
+
+const HAND_SIZE: u32 = 5;
+
+/// How many hands fell into each category, indexed by `Hand::score`.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    counts: [usize; 8],
+    total: usize,
+}
+
+impl Distribution {
+    fn new() -> Distribution {
+        Distribution { counts: [0; 8], total: 0 }
+    }
+
+    fn record(&mut self, hand: String, rules: Rules) {
+        self.counts[rules.classify(hand).score()] += 1;
+        self.total += 1;
+    }
+
+    /// Classifies every one of the 13^5 possible hands.
+    pub fn exhaustive(rules: Rules) -> Distribution {
+        let cards = rules.card_values();
+        let mut distribution = Distribution::new();
+
+        for index in 0..cards.len().pow(HAND_SIZE) {
+            distribution.record(hand_at(cards, index), rules);
+        }
+
+        distribution
+    }
+
+    /// Classifies `samples` hands drawn uniformly at random from a generator seeded with `seed`.
+    pub fn sampled(rules: Rules, samples: usize, seed: u64) -> Distribution {
+        let cards = rules.card_values();
+        let mut rng = Rng::new(seed);
+        let mut distribution = Distribution::new();
+
+        for _ in 0..samples {
+            let hand = (0..HAND_SIZE)
+                .map(|_| cards[rng.below(cards.len())])
+                .collect();
+            distribution.record(hand, rules);
+        }
+
+        distribution
+    }
+
+    /// Classifies the hands of a puzzle input, ignoring the bets.
+    pub fn observed(input: &str, rules: Rules) -> Distribution {
+        let mut distribution = Distribution::new();
+
+        for line in input.lines() {
+            if let Some(hand) = line.split_ascii_whitespace().next() {
+                distribution.record(hand.to_string(), rules);
+            }
+        }
+
+        distribution
+    }
+
+    pub fn probability(&self, score: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.counts[score] as f64 / self.total as f64
+    }
+
+    /// Pearson's chi-squared statistic of this distribution against the probabilities of `expected`.
+    /// Categories that `expected` never produces are left out, along with their degree of freedom.
+    pub fn chi_squared(&self, expected: &Distribution) -> (f64, usize) {
+        let mut statistic = 0.0;
+        let mut categories: usize = 0;
+
+        for score in 0..self.counts.len() {
+            let expected_count = expected.probability(score) * self.total as f64;
+            if expected_count == 0.0 {
+                continue;
+            }
+
+            let difference = self.counts[score] as f64 - expected_count;
+            statistic += difference * difference / expected_count;
+            categories += 1;
+        }
+
+        (statistic, categories.saturating_sub(1))
+    }
+}
+
+/// Decodes `index` as a base-13 number whose digits pick the cards of the hand.
+fn hand_at(cards: &[char], mut index: usize) -> String {
+    let mut hand = String::with_capacity(HAND_SIZE as usize);
+
+    for _ in 0..HAND_SIZE {
+        hand.push(cards[index % cards.len()]);
+        index /= cards.len();
+    }
+
+    hand
+}
+
+/// Small xorshift64* generator so sampled runs are reproducible without extra dependencies.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // The all-zero state is a fixed point of xorshift, so nudge it away.
+        Rng { state: seed ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn print_table(columns: &[(&str, &Distribution)]) {
+    print!("{:<16}", "Category");
+    for (title, _) in columns {
+        print!(" {:>12}", title);
+    }
+    println!();
+
+    for (score, name) in CATEGORY_NAMES.iter().enumerate() {
+        if columns.iter().all(|(_, distribution)| distribution.counts[score] == 0) {
+            continue;
+        }
+
+        print!("{:<16}", name);
+        for (_, distribution) in columns {
+            print!(" {:>12.6}", distribution.probability(score));
+        }
+        println!();
+    }
+}
+
+/// `day7 stats [--jokers] [--samples N] [--seed S]`
+pub fn run(input: &str, args: &[String]) {
+    let mut rules = Rules::Standard;
+    let mut samples = 100_000;
+    let mut seed = 2023;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--jokers" => rules = Rules::Jokers,
+            "--samples" => samples = args.next().and_then(|n| n.parse().ok()).expect("--samples needs a number"),
+            "--seed" => seed = args.next().and_then(|n| n.parse().ok()).expect("--seed needs a number"),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let exhaustive = Distribution::exhaustive(rules);
+    let sampled = Distribution::sampled(rules, samples, seed);
+    let observed = Distribution::observed(input, rules);
+
+    println!("Rules: {:?}", rules);
+    print_table(&[("Exhaustive", &exhaustive), ("Sampled", &sampled), ("Input", &observed)]);
+
+    let (sampled_statistic, sampled_freedom) = sampled.chi_squared(&exhaustive);
+    println!("Sampled ({} hands, seed {}): chi-squared {:.3} with {} degrees of freedom", sampled.total, seed, sampled_statistic, sampled_freedom);
+
+    let (observed_statistic, observed_freedom) = observed.chi_squared(&exhaustive);
+    println!("Input ({} hands): chi-squared {:.3} with {} degrees of freedom", observed.total, observed_statistic, observed_freedom);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_rules_match_the_known_counts() {
+        let distribution = Distribution::exhaustive(Rules::Standard);
+        assert_eq!(distribution.counts, [0, 154440, 171600, 25740, 17160, 1560, 780, 13]);
+        assert_eq!(distribution.total, 13usize.pow(HAND_SIZE));
+    }
+
+    #[test]
+    fn joker_rules_match_the_known_counts() {
+        let distribution = Distribution::exhaustive(Rules::Jokers);
+        assert_eq!(distribution.counts, [0, 95040, 178200, 19800, 66000, 3300, 8580, 373]);
+    }
+
+    #[test]
+    fn hand_at_reads_the_index_in_base_thirteen() {
+        let cards = Rules::Standard.card_values();
+        assert_eq!(hand_at(cards, 0), "AAAAA");
+        assert_eq!(hand_at(cards, 1), "KAAAA");
+        assert_eq!(hand_at(cards, 13), "AKAAA");
+        assert_eq!(hand_at(cards, 13usize.pow(HAND_SIZE) - 1), "22222");
+    }
+
+    #[test]
+    fn chi_squared_is_zero_for_matching_distributions() {
+        let expected = Distribution { counts: [0, 50, 30, 20, 0, 0, 0, 0], total: 100 };
+        let observed = Distribution { counts: [0, 5, 3, 2, 0, 0, 0, 0], total: 10 };
+        assert_eq!(observed.chi_squared(&expected), (0.0, 2));
+
+        // (10 - 5)^2 / 5 + (0 - 3)^2 / 3 + (0 - 2)^2 / 2
+        let skewed = Distribution { counts: [0, 10, 0, 0, 0, 0, 0, 0], total: 10 };
+        let (statistic, freedom) = skewed.chi_squared(&expected);
+        assert!((statistic - 10.0).abs() < 1e-9);
+        assert_eq!(freedom, 2);
+    }
+
+    #[test]
+    fn sampling_is_reproducible_for_a_seed() {
+        let first = Distribution::sampled(Rules::Jokers, 1000, 7);
+        let second = Distribution::sampled(Rules::Jokers, 1000, 7);
+        let other = Distribution::sampled(Rules::Jokers, 1000, 8);
+
+        assert_eq!(first.counts, second.counts);
+        assert_ne!(first.counts, other.counts);
+        assert_eq!(first.total, 1000);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{lcm, Map};
+use crate::matcher::NodeMatcher;
+
+/// The shape of one ghost's walk. Since the state of a walk is the current node plus the position
+/// in the route, the walk must eventually revisit a state and loop from there on.
+#[derive(Debug, Clone)]
+pub struct GhostCycle {
+    pub start: usize,
+    /// Step at which the walk first enters the state it later returns to.
+    pub pre_period: usize,
+    pub cycle_length: usize,
+    /// Steps before `pre_period` that land on an end node. Each of these happens exactly once.
+    pub transient_hits: Vec<usize>,
+    /// Steps in `pre_period..pre_period + cycle_length` that land on an end node. Each of these
+    /// repeats every `cycle_length` steps.
+    pub cycle_hits: Vec<usize>,
+}
+
+impl GhostCycle {
+    pub fn analyse(map: &Map, start: usize, end: &NodeMatcher) -> GhostCycle {
+        let end = map.mask(end);
+        let mut map = map.clone();
+        map.current_node = start;
+        map.current_step = 0;
+
+        let route_len = map.route.len();
+        let mut first_seen = vec![usize::MAX; map.nodes.len() * route_len];
+        let mut hits = Vec::new();
+
+        loop {
+            let state = map.current_node * route_len + map.current_step % route_len;
+            if first_seen[state] != usize::MAX {
+                let pre_period = first_seen[state];
+                let (transient_hits, cycle_hits) = hits.iter().partition(|&&hit| hit < pre_period);
+
+                return GhostCycle {
+                    start,
+                    pre_period,
+                    cycle_length: map.current_step - pre_period,
+                    transient_hits,
+                    cycle_hits,
+                };
+            }
+
+            first_seen[state] = map.current_step;
+            if end[map.current_node] {
+                hits.push(map.current_step);
+            }
+
+            map.next();
+        }
+    }
+
+    pub fn is_hit(&self, step: usize) -> bool {
+        if step < self.pre_period {
+            return self.transient_hits.contains(&step);
+        }
+
+        let offset = (step - self.pre_period) % self.cycle_length;
+        self.cycle_hits.contains(&(self.pre_period + offset))
+    }
+
+    /// Whether the ghost satisfies the assumption behind taking the LCM of cycle lengths: it
+    /// reaches an end node only at the multiples of its cycle length.
+    pub fn lcm_compatible(&self) -> bool {
+        self.transient_hits.is_empty() && self.cycle_hits == [self.cycle_length]
+    }
+}
+
+/// Extended Euclid over signed integers, returning `(g, x, y)` with `a * x + b * y == g`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+/// Merges `x = a1 (mod m1)` and `x = a2 (mod m2)` into a single congruence, if they are compatible.
+/// The moduli need not be coprime.
+fn crt(a1: i128, m1: i128, a2: i128, m2: i128) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+
+    let modulus = m1 / g * m2;
+    let k = ((a2 - a1) / g % (m2 / g)) * p % (m2 / g);
+    let a = (a1 + m1 * k).rem_euclid(modulus);
+    Some((a, modulus))
+}
+
+/// The earliest step at which every ghost stands on an end node at once, if there is one.
+pub fn first_common_hit(ghosts: &[GhostCycle]) -> Option<usize> {
+    let settled = ghosts.iter().map(|ghost| ghost.pre_period).max()?;
+
+    // Before every ghost has entered its loop there are only finitely many steps to check.
+    if let Some(step) = (0..settled).find(|&step| ghosts.iter().all(|ghost| ghost.is_hit(step))) {
+        return Some(step);
+    }
+
+    // From `settled` on, every hit is periodic, so each choice of one hit per ghost is a system of
+    // congruences. Try all of them and keep the smallest solution.
+    let mut systems: Vec<(i128, i128)> = vec![(0, 1)];
+    for ghost in ghosts {
+        let length = ghost.cycle_length as i128;
+        systems = systems
+            .iter()
+            .flat_map(|&(a, m)| {
+                ghost.cycle_hits
+                    .iter()
+                    .filter_map(move |&hit| crt(a, m, hit as i128 % length, length))
+            })
+            .collect();
+    }
+
+    systems
+        .iter()
+        .map(|&(a, m)| {
+            let settled = settled as i128;
+            if a >= settled {
+                a
+            } else {
+                a + (settled - a + m - 1) / m * m
+            }
+        })
+        .min()
+        .map(|step| step as usize)
+}
+
+/// Prints each ghost's cycle and the combined answer, noting whether the LCM shortcut holds.
+pub fn report(ghosts: &[GhostCycle], map: &Map) {
+    for ghost in ghosts {
+        println!(
+            "{}: pre-period {}, cycle length {}, transient hits {:?}, cycle hits {:?}{}",
+            map.names[ghost.start],
+            ghost.pre_period,
+            ghost.cycle_length,
+            ghost.transient_hits,
+            ghost.cycle_hits,
+            if ghost.lcm_compatible() { "" } else { " (not LCM compatible)" },
+        );
+    }
+
+    let shortcut = ghosts.iter().fold(1, |acc, ghost| lcm(acc, ghost.cycle_length));
+    match first_common_hit(ghosts) {
+        Some(step) if step == shortcut => println!("LCM shortcut is valid: {}", shortcut),
+        Some(step) => println!("LCM shortcut gives {} but the first common hit is {}", shortcut, step),
+        None => println!("Ghosts never reach end nodes together; LCM shortcut would give {}", shortcut),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    const EXAMPLE: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+
+    fn ghost(map: &Map, name: &str) -> GhostCycle {
+        GhostCycle::analyse(map, map.ids[name], &NodeMatcher::Suffix("Z".to_string()))
+    }
+
+    fn periodic(cycle_length: usize, cycle_hits: Vec<usize>) -> GhostCycle {
+        GhostCycle { start: 0, pre_period: 0, cycle_length, transient_hits: Vec::new(), cycle_hits }
+    }
+
+    #[test]
+    fn crt_merges_coprime_moduli() {
+        assert_eq!(crt(2, 3, 3, 5), Some((8, 15)));
+    }
+
+    #[test]
+    fn crt_merges_moduli_sharing_a_factor() {
+        assert_eq!(crt(2, 4, 4, 6), Some((10, 12)));
+        assert_eq!(crt(3, 6, 3, 9), Some((3, 18)));
+    }
+
+    #[test]
+    fn crt_rejects_incompatible_congruences() {
+        // One asks for an odd step, the other for an even one.
+        assert_eq!(crt(1, 4, 2, 6), None);
+    }
+
+    #[test]
+    fn example_ghosts() {
+        let map = parse_input(EXAMPLE).unwrap();
+
+        let first = ghost(&map, "11A");
+        assert_eq!((first.pre_period, first.cycle_length), (1, 2));
+        assert_eq!(first.cycle_hits, [2]);
+        assert!(first.lcm_compatible());
+
+        let second = ghost(&map, "22A");
+        assert_eq!((second.pre_period, second.cycle_length), (1, 6));
+        assert_eq!(second.cycle_hits, [3, 6]);
+        assert!(!second.lcm_compatible());
+
+        assert_eq!(first_common_hit(&[first, second]), Some(6));
+    }
+
+    #[test]
+    fn transient_hits_come_before_the_loop() {
+        let map = parse_input("L\n\nAAA = (BBZ, BBZ)\nBBZ = (CCC, CCC)\nCCC = (CCC, CCC)").unwrap();
+        let ghost = ghost(&map, "AAA");
+
+        assert_eq!((ghost.pre_period, ghost.cycle_length), (2, 1));
+        assert_eq!(ghost.transient_hits, [1]);
+        assert!(ghost.cycle_hits.is_empty());
+        assert!(ghost.is_hit(1));
+        assert!(!ghost.is_hit(5));
+        assert_eq!(first_common_hit(&[ghost]), Some(1));
+    }
+
+    #[test]
+    fn several_hits_per_cycle() {
+        let map = parse_input("L\n\nAAA = (11Z, 11Z)\n11Z = (BBB, BBB)\nBBB = (22Z, 22Z)\n22Z = (AAA, AAA)").unwrap();
+        let ghost = ghost(&map, "AAA");
+
+        assert_eq!((ghost.pre_period, ghost.cycle_length), (0, 4));
+        assert_eq!(ghost.cycle_hits, [1, 3]);
+        assert!(!ghost.lcm_compatible());
+        assert!(ghost.is_hit(5));
+        assert!(!ghost.is_hit(6));
+
+        // Paired with a ghost hitting every third step, the first common hit is 3, not the LCM 12.
+        assert_eq!(first_common_hit(&[ghost, periodic(3, vec![3])]), Some(3));
+    }
+
+    #[test]
+    fn ghosts_that_never_meet() {
+        assert_eq!(first_common_hit(&[periodic(2, vec![1]), periodic(4, vec![2, 4])]), None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::Map;
+use crate::matcher::NodeMatcher;
+
+/// Walks `steps` steps from `start`, returning each edge taken as `(from, to)`.
+pub fn walk(map: &Map, start: usize, steps: usize) -> Vec<(usize, usize)> {
+    let mut map = map.clone();
+    map.current_node = start;
+    map.current_step = 0;
+
+    let mut path = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let from = map.current_node;
+        map.next();
+        path.push((from, map.current_node));
+    }
+    path
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\\\""))
+}
+
+/// Renders the network as a Graphviz digraph. Start and end nodes are styled, and the nodes and
+/// edges of `path` are drawn in a highlight colour.
+pub fn to_dot(map: &Map, start: &NodeMatcher, end: &NodeMatcher, path: &[(usize, usize)]) -> String {
+    let highlighted_edges: HashSet<(usize, usize)> = path.iter().copied().collect();
+    let highlighted_nodes: HashSet<usize> = path.iter().flat_map(|&(from, to)| [from, to]).collect();
+
+    let mut dot = String::from("digraph network {\n");
+
+    for (id, name) in map.names.iter().enumerate() {
+        let mut attributes = Vec::new();
+        if start.matches(name) {
+            attributes.push("shape=box, style=filled, fillcolor=palegreen");
+        } else if end.matches(name) {
+            attributes.push("shape=doublecircle, style=filled, fillcolor=lightpink");
+        }
+        if highlighted_nodes.contains(&id) {
+            attributes.push("color=blue, penwidth=2");
+        }
+
+        dot.push_str(&format!("    {}", quote(name)));
+        if !attributes.is_empty() {
+            dot.push_str(&format!(" [{}]", attributes.join(", ")));
+        }
+        dot.push_str(";\n");
+    }
+
+    for (id, &(left, right)) in map.nodes.iter().enumerate() {
+        let edges: Vec<(usize, &str)> = if left == right {
+            vec![(left, "L/R")]
+        } else {
+            vec![(left, "L"), (right, "R")]
+        };
+
+        for (to, label) in edges {
+            let style = if highlighted_edges.contains(&(id, to)) { ", color=blue, penwidth=2" } else { "" };
+            dot.push_str(&format!("    {} -> {} [label=\"{}\"{}];\n", quote(&map.names[id]), quote(&map.names[to]), label, style));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    fn example() -> Map {
+        parse_input("LR\n\nAAA = (BBB, CCC)\nBBB = (ZZZ, ZZZ)\nCCC = (CCC, CCC)\nZZZ = (ZZZ, AAA)").unwrap()
+    }
+
+    #[test]
+    fn walk_follows_the_route() {
+        let map = example();
+        let (aaa, bbb, zzz) = (map.ids["AAA"], map.ids["BBB"], map.ids["ZZZ"]);
+
+        assert_eq!(walk(&map, aaa, 3), [(aaa, bbb), (bbb, zzz), (zzz, zzz)]);
+        assert!(walk(&map, aaa, 0).is_empty());
+    }
+
+    #[test]
+    fn renders_nodes_and_edges() {
+        let map = example();
+        let start = NodeMatcher::Exact("AAA".to_string());
+        let end = NodeMatcher::Exact("ZZZ".to_string());
+        let path = walk(&map, map.ids["AAA"], 2);
+
+        assert_eq!(
+            to_dot(&map, &start, &end, &path),
+            "digraph network {
+    \"AAA\" [shape=box, style=filled, fillcolor=palegreen, color=blue, penwidth=2];
+    \"BBB\" [color=blue, penwidth=2];
+    \"CCC\";
+    \"ZZZ\" [shape=doublecircle, style=filled, fillcolor=lightpink, color=blue, penwidth=2];
+    \"AAA\" -> \"BBB\" [label=\"L\", color=blue, penwidth=2];
+    \"AAA\" -> \"CCC\" [label=\"R\"];
+    \"BBB\" -> \"ZZZ\" [label=\"L/R\", color=blue, penwidth=2];
+    \"CCC\" -> \"CCC\" [label=\"L/R\"];
+    \"ZZZ\" -> \"ZZZ\" [label=\"L\"];
+    \"ZZZ\" -> \"AAA\" [label=\"R\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn quotes_names() {
+        assert_eq!(quote("A\"B"), "\"A\\\"B\"");
+    }
+}
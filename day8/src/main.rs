@@ -1,87 +1,162 @@
 use std::collections::HashMap;
 
+mod cycles;
+mod dot;
+mod matcher;
+mod trace;
+
+use cycles::GhostCycle;
+use matcher::NodeMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
 #[derive(Debug, Clone)]
 struct Map {
-    route: String,
-    nodes: HashMap<String, (String, String)>,
-    current_node: String,
+    route: Vec<Direction>,
+    /// Node names indexed by id, the reverse of `ids`.
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// Left and right neighbours of each node, indexed by id.
+    nodes: Vec<(usize, usize)>,
+    current_node: usize,
     current_step: usize,
 }
 
 impl Map {
-    fn steps_to_end(&mut self) -> usize {
-        loop {
-            if self.current_node == "ZZZ" {
-                return self.current_step;
-            }
-
-            self.next();
-        }
+    /// Flags the nodes `matcher` picks out, indexed by id, so walks never have to look at names.
+    fn mask(&self, matcher: &NodeMatcher) -> Vec<bool> {
+        self.names.iter().map(|name| matcher.matches(name)).collect()
     }
 
-    fn steps_to_cycle(&mut self, starting_point: String) -> usize {
-        let mut iter_counts: Vec<usize> = Vec::new();
-        let mut starting_step = 0;
+    /// Walks from the current node until it stands on an end node. The walk's state is its node
+    /// plus its position in the route, so once a state repeats the walk is known to loop forever
+    /// without reaching the end, and that is reported instead.
+    fn steps_to_end(&mut self, end: &NodeMatcher) -> Result<usize, String> {
+        let end = self.mask(end);
+        if !end.contains(&true) {
+            return Err("No node matches the end condition".to_string());
+        }
 
-        self.current_node = starting_point;
+        let start = self.current_node;
+        let route_len = self.route.len();
+        let mut seen = vec![false; self.nodes.len() * route_len];
 
         loop {
-            if self.current_node.ends_with("Z") {
-                let steps_taken = self.current_step - starting_step;
-                if iter_counts.contains(&(&steps_taken)) {
-                    return steps_taken; 
-                } else {
-                    iter_counts.push(steps_taken);
-                    starting_step = self.current_step;
-                }
+            if end[self.current_node] {
+                return Ok(self.current_step);
+            }
+
+            let state = self.current_node * route_len + self.current_step % route_len;
+            if seen[state] {
+                return Err(format!(
+                    "{} never reaches an end node, the walk starts repeating itself after {} steps",
+                    self.names[start], self.current_step
+                ));
             }
+            seen[state] = true;
 
             self.next();
         }
     }
 
-    fn next(&mut self) {
-        let (left, right) = self.nodes.get(&self.current_node).unwrap();
-        let current_dir = self.route.chars().nth(self.current_step % self.route.len()).unwrap();
+    /// Takes one step along the route, returning the direction that was followed.
+    fn next(&mut self) -> Direction {
+        let (left, right) = self.nodes[self.current_node];
+        let direction = self.route[self.current_step % self.route.len()];
 
-        self.current_node = match current_dir {
-            'L' => left.to_string(),
-            'R' => right.to_string(),
-            _ => panic!("Unknown direction {}", current_dir),
+        self.current_node = match direction {
+            Direction::Left => left,
+            Direction::Right => right,
         };
-        self.current_step = self.current_step + 1;
+        self.current_step += 1;
+        direction
     }
 
-    fn start_nodes(&self) -> Vec<String> {
+    fn start_nodes(&self, start: &NodeMatcher) -> Vec<usize> {
         let mut nodes = Vec::new();
-        for (node, ..) in self.nodes.iter() {
-            if node.ends_with("A") {
-                nodes.push(node.to_string());
+        for (id, name) in self.names.iter().enumerate() {
+            if start.matches(name) {
+                nodes.push(id);
             }
         }
         nodes
     }
 }
 
-fn parse_input(input: &str) -> Map {
+/// Returns the id for `name`, handing out the next free one the first time a name is seen.
+fn intern(name: &str, names: &mut Vec<String>, ids: &mut HashMap<String, usize>) -> usize {
+    if let Some(&id) = ids.get(name) {
+        return id;
+    }
+
+    let id = names.len();
+    names.push(name.to_string());
+    ids.insert(name.to_string(), id);
+    id
+}
+
+fn parse_input(input: &str) -> Result<Map, String> {
     let mut lines = input.lines();
-    let route = lines.next().unwrap().to_string();
+    let route: Vec<Direction> = lines.next()
+        .ok_or("Missing route")?
+        .chars()
+        .map(|c| match c {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(format!("Unknown direction {}", c)),
+        })
+        .collect::<Result<_, _>>()?;
+
+    if route.is_empty() {
+        return Err("Empty route".to_string());
+    }
 
     // Skip empty line
     lines.next();
 
-    let mut nodes = HashMap::new();
+    let mut names = Vec::new();
+    let mut ids = HashMap::new();
+    let mut edges = Vec::new();
     for line in lines {
-        let mut parts = line.split(" = ");
-        let node = parts.next().unwrap().to_string();
-        let left_right_part_str = parts.next().unwrap();
-        let mut left_right_parts = left_right_part_str[1..left_right_part_str.len() - 1].split(", ");
-        let left = left_right_parts.next().unwrap().to_string();
-        let right = left_right_parts.next().unwrap().to_string();
-        nodes.insert(node, (left, right));
+        let (node, left_right) = line.split_once(" = ")
+            .ok_or_else(|| format!("Malformed node line: {}", line))?;
+        let (left, right) = left_right
+            .strip_prefix('(')
+            .and_then(|left_right| left_right.strip_suffix(')'))
+            .and_then(|left_right| left_right.split_once(", "))
+            .ok_or_else(|| format!("Malformed node line: {}", line))?;
+
+        let node = intern(node, &mut names, &mut ids);
+        let left = intern(left, &mut names, &mut ids);
+        let right = intern(right, &mut names, &mut ids);
+        edges.push((node, (left, right)));
     }
 
-    Map { route, nodes, current_node: "AAA".to_string(), current_step: 0 }
+    let mut nodes = vec![None; names.len()];
+    for &(node, left_right) in edges.iter() {
+        if nodes[node].is_some() {
+            return Err(format!("Node {} is defined more than once", names[node]));
+        }
+        nodes[node] = Some(left_right);
+    }
+
+    let dangling: Vec<String> = edges.iter()
+        .flat_map(|&(node, (left, right))| [(node, left), (node, right)])
+        .filter(|&(_, to)| nodes[to].is_none())
+        .map(|(from, to)| format!("{} (referenced by {})", names[to], names[from]))
+        .collect();
+
+    if !dangling.is_empty() {
+        return Err(format!("Undefined nodes: {}", dangling.join(", ")));
+    }
+
+    let nodes = nodes.into_iter().flatten().collect();
+
+    Ok(Map { route, names, ids, nodes, current_node: 0, current_step: 0 })
 }
 
 fn gcd(a: usize, b: usize) -> usize {
@@ -96,29 +171,226 @@ fn lcm(a: usize, b: usize) -> usize {
     a * b / gcd(a, b)
 }
 
-fn part_one(input: &str) {
-    let mut map = parse_input(input);
-    println!("Part one: {}", map.steps_to_end());
+fn part_one(map: &Map) {
+    let Some(&start) = map.ids.get("AAA") else {
+        println!("Part one: there is no AAA node");
+        return;
+    };
+
+    let mut map = map.clone();
+    map.current_node = start;
+    match map.steps_to_end(&NodeMatcher::Exact("ZZZ".to_string())) {
+        Ok(steps) => println!("Part one: {}", steps),
+        Err(e) => println!("Part one: {}", e),
+    }
 }
 
-fn part_two(input: &str) {
-    let map = parse_input(input);
+fn ghost_cycles(map: &Map, start: &NodeMatcher, end: &NodeMatcher) -> Vec<GhostCycle> {
+    map.start_nodes(start)
+        .into_iter()
+        .map(|start_node| GhostCycle::analyse(map, start_node, end))
+        .collect()
+}
 
-    let start_nodes = map.start_nodes();
+fn part_two(map: &Map) {
+    let ghosts = ghost_cycles(map, &NodeMatcher::Suffix("A".to_string()), &NodeMatcher::Suffix("Z".to_string()));
 
-    let mut cycles = Vec::new();
-    for start_node in start_nodes {
-        let mut map = map.clone();
-        cycles.push(map.steps_to_cycle(start_node));
+    match cycles::first_common_hit(&ghosts) {
+        Some(steps) => println!("Part two: {}", steps),
+        None => println!("Part two: the ghosts never all reach an end node together"),
     }
+}
 
-    let smallest_cycle = cycles.iter().fold(cycles[0], |acc, &x| lcm(acc, x));
+/// Pulls `--start SPEC` and `--end SPEC` out of `args`, defaulting to the part two `..A` and `..Z`
+/// nodes, and returns them along with the remaining arguments.
+fn parse_endpoints(args: &[String]) -> (NodeMatcher, NodeMatcher, Vec<String>) {
+    let mut start = NodeMatcher::Suffix("A".to_string());
+    let mut end = NodeMatcher::Suffix("Z".to_string());
+    let mut rest = Vec::new();
 
-    println!("Part two: {}", smallest_cycle);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" | "--end" => {
+                let spec = args.next().unwrap_or_else(|| panic!("{} needs a matcher", arg));
+                let matcher = NodeMatcher::parse(spec).unwrap_or_else(|e| panic!("{}", e));
+                if arg == "--start" {
+                    start = matcher;
+                } else {
+                    end = matcher;
+                }
+            },
+            _ => rest.push(arg.to_string()),
+        }
+    }
+
+    (start, end, rest)
 }
 
+/// Prints the steps each start node needs to reach an end node.
+fn walk(map: &Map, start: &NodeMatcher, end: &NodeMatcher) {
+    for start_node in map.start_nodes(start) {
+        let mut walker = map.clone();
+        walker.current_node = start_node;
+        match walker.steps_to_end(end) {
+            Ok(steps) => println!("{}: {}", map.names[start_node], steps),
+            Err(e) => println!("{}: {}", map.names[start_node], e),
+        }
+    }
+}
+
+fn export_dot(map: &Map, start: &NodeMatcher, end: &NodeMatcher, args: &[String]) {
+    let mut path = Vec::new();
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--path" => {
+                for start_node in map.start_nodes(start) {
+                    let mut walker = map.clone();
+                    walker.current_node = start_node;
+                    let steps = walker.steps_to_end(end).unwrap_or_else(|e| panic!("{}", e));
+                    path.extend(dot::walk(map, start_node, steps));
+                }
+            },
+            "--ghost" => {
+                let name = args.next().expect("--ghost needs a node name");
+                let start = *map.ids.get(name).unwrap_or_else(|| panic!("Unknown node {}", name));
+                let ghost = GhostCycle::analyse(map, start, end);
+                path.extend(dot::walk(map, start, ghost.pre_period + ghost.cycle_length));
+            },
+            "--output" => output = Some(args.next().expect("--output needs a file name")),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let dot = dot::to_dot(map, start, end, &path);
+    match output {
+        Some(file) => {
+            if let Err(e) = std::fs::write(file, dot) {
+                eprintln!("Could not write {}: {}", file, e);
+                std::process::exit(1);
+            }
+        },
+        None => print!("{}", dot),
+    }
+}
+
+/// Every subcommand also accepts `--start SPEC` and `--end SPEC`, where a spec is one of
+/// `exact:NAME`, `suffix:TEXT`, `regex:PATTERN` or `set:NAME,NAME,...`.
 fn main() {
     let input = include_str!("input.txt");
-    part_one(input);
-    part_two(input);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (start, end, args) = parse_endpoints(&args);
+
+    // Replaying only needs the trace file, not the network.
+    if args.first().is_some_and(|arg| arg == "replay") {
+        if let Err(e) = trace::replay(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let map = match parse_input(input) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Invalid network: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("walk") => walk(&map, &start, &end),
+        Some("cycles") => cycles::report(&ghost_cycles(&map, &start, &end), &map),
+        Some("dot") => export_dot(&map, &start, &end, &args[1..]),
+        Some("trace") => {
+            if let Err(e) = trace::run(&map, &start, &end, &args[1..]) {
+                eprintln!("Could not write trace: {}", e);
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            part_one(&map);
+            part_two(&map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+
+    fn steps_from(map: &Map, name: &str, end: &NodeMatcher) -> Result<usize, String> {
+        let mut map = map.clone();
+        map.current_node = map.ids[name];
+        map.steps_to_end(end)
+    }
+
+    #[test]
+    fn interns_names_in_order_of_appearance() {
+        let map = parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(map.names, ["AAA", "BBB", "ZZZ"]);
+        for (id, name) in map.names.iter().enumerate() {
+            assert_eq!(map.ids[name], id);
+        }
+        assert_eq!(map.nodes, [(1, 1), (0, 2), (2, 2)]);
+        assert_eq!(map.route, [Direction::Left, Direction::Left, Direction::Right]);
+    }
+
+    #[test]
+    fn interns_forward_references() {
+        // BBB and CCC are referenced before their own lines.
+        let map = parse_input("L\n\nAAA = (BBB, CCC)\nCCC = (AAA, AAA)\nBBB = (CCC, BBB)").unwrap();
+
+        assert_eq!(map.names, ["AAA", "BBB", "CCC"]);
+        assert_eq!(map.nodes, [(1, 2), (2, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn walks_the_example() {
+        let map = parse_input(EXAMPLE).unwrap();
+        assert_eq!(steps_from(&map, "AAA", &NodeMatcher::Exact("ZZZ".to_string())), Ok(6));
+    }
+
+    #[test]
+    fn rejects_duplicate_nodes() {
+        let error = parse_input("L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!(error, "Node AAA is defined more than once");
+    }
+
+    #[test]
+    fn reports_dangling_nodes() {
+        let error = parse_input("L\n\nAAA = (BBB, CCC)\nBBB = (AAA, DDD)").unwrap_err();
+        assert_eq!(error, "Undefined nodes: CCC (referenced by AAA), DDD (referenced by BBB)");
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(parse_input("").unwrap_err(), "Missing route");
+        assert_eq!(parse_input("LX\n\nAAA = (AAA, AAA)").unwrap_err(), "Unknown direction X");
+        assert_eq!(parse_input("L\n\nAAA = AAA, AAA").unwrap_err(), "Malformed node line: AAA = AAA, AAA");
+    }
+
+    #[test]
+    fn reports_unreachable_ends() {
+        let map = parse_input("L\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)").unwrap();
+
+        assert_eq!(
+            steps_from(&map, "AAA", &NodeMatcher::Exact("ZZZ".to_string())),
+            Err("AAA never reaches an end node, the walk starts repeating itself after 2 steps".to_string())
+        );
+        assert_eq!(
+            steps_from(&map, "AAA", &NodeMatcher::Exact("YYY".to_string())),
+            Err("No node matches the end condition".to_string())
+        );
+    }
 }
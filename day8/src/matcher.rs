@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+
+/// Picks out a set of nodes by name, used to choose where walks start and where they end.
+#[derive(Debug, Clone)]
+pub enum NodeMatcher {
+    Exact(String),
+    Suffix(String),
+    Regex(Regex),
+    Set(HashSet<String>),
+}
+
+impl NodeMatcher {
+    /// Parses `exact:NAME`, `suffix:TEXT`, `regex:PATTERN` or `set:NAME,NAME,...`.
+    pub fn parse(spec: &str) -> Result<NodeMatcher, String> {
+        let (kind, value) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("Expected KIND:VALUE, got {}", spec))?;
+
+        match kind {
+            "exact" => Ok(NodeMatcher::Exact(value.to_string())),
+            "suffix" => Ok(NodeMatcher::Suffix(value.to_string())),
+            "regex" => Regex::new(value)
+                .map(NodeMatcher::Regex)
+                .map_err(|e| format!("Invalid regex {}: {}", value, e)),
+            "set" => Ok(NodeMatcher::Set(value.split(',').map(|name| name.to_string()).collect())),
+            _ => Err(format!("Unknown matcher kind {}", kind)),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NodeMatcher::Exact(exact) => name == exact,
+            NodeMatcher::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodeMatcher::Regex(regex) => regex.is_match(name),
+            NodeMatcher::Set(names) => names.contains(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_kind() {
+        let exact = NodeMatcher::parse("exact:AAA").unwrap();
+        assert!(exact.matches("AAA"));
+        assert!(!exact.matches("BAAA"));
+
+        let suffix = NodeMatcher::parse("suffix:Z").unwrap();
+        assert!(suffix.matches("11Z"));
+        assert!(!suffix.matches("Z11"));
+
+        let regex = NodeMatcher::parse("regex:^1.Z$").unwrap();
+        assert!(regex.matches("11Z"));
+        assert!(!regex.matches("22Z"));
+
+        let set = NodeMatcher::parse("set:AAA,11A").unwrap();
+        assert!(set.matches("AAA"));
+        assert!(set.matches("11A"));
+        assert!(!set.matches("22A"));
+    }
+
+    #[test]
+    fn keeps_colons_in_the_value() {
+        assert!(NodeMatcher::parse("exact:A:B").unwrap().matches("A:B"));
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert_eq!(NodeMatcher::parse("AAA").unwrap_err(), "Expected KIND:VALUE, got AAA");
+        assert_eq!(NodeMatcher::parse("prefix:A").unwrap_err(), "Unknown matcher kind prefix");
+        assert!(NodeMatcher::parse("regex:(").unwrap_err().starts_with("Invalid regex ("));
+    }
+}
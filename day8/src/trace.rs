@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::cycles::GhostCycle;
+use crate::matcher::NodeMatcher;
+use crate::{Direction, Map};
+
+/// Which steps of a walk end up in the trace. A step is kept only if it passes every filter set.
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    pub every: Option<usize>,
+    pub hits_only: bool,
+}
+
+/// One step of `Map::next` as written to a trace file.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub walker: String,
+    pub step: usize,
+    pub direction: Direction,
+    pub from: String,
+    pub to: String,
+    /// Whether `to` is an end node.
+    pub hit: bool,
+}
+
+impl Record {
+    fn to_line(&self) -> String {
+        let direction = match self.direction {
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        };
+        format!("{} {} {} {} {} {}", self.walker, self.step, direction, self.from, self.to, if self.hit { "hit" } else { "-" })
+    }
+
+    fn from_line(line: &str) -> Result<Record, String> {
+        let parts: Vec<&str> = line.split_ascii_whitespace().collect();
+        if parts.len() != 6 {
+            return Err(format!("Malformed trace line: {}", line));
+        }
+
+        Ok(Record {
+            walker: parts[0].to_string(),
+            step: parts[1].parse().map_err(|_| format!("Invalid step in trace line: {}", line))?,
+            direction: match parts[2] {
+                "L" => Direction::Left,
+                "R" => Direction::Right,
+                _ => return Err(format!("Invalid direction in trace line: {}", line)),
+            },
+            from: parts[3].to_string(),
+            to: parts[4].to_string(),
+            hit: parts[5] == "hit",
+        })
+    }
+}
+
+/// Walks `steps` steps from `start`, writing every step that passes `filter` to `out`.
+pub fn record(map: &Map, start: usize, end: &NodeMatcher, steps: usize, filter: &TraceFilter, out: &mut impl Write) -> io::Result<()> {
+    let end = map.mask(end);
+    let mut map = map.clone();
+    map.current_node = start;
+    map.current_step = 0;
+
+    for _ in 0..steps {
+        let from = map.current_node;
+        let step = map.current_step;
+        let direction = map.next();
+        let hit = end[map.current_node];
+
+        if filter.every.is_some_and(|every| !step.is_multiple_of(every)) || (filter.hits_only && !hit) {
+            continue;
+        }
+
+        let record = Record {
+            walker: map.names[start].to_string(),
+            step,
+            direction,
+            from: map.names[from].to_string(),
+            to: map.names[map.current_node].to_string(),
+            hit,
+        };
+        writeln!(out, "{}", record.to_line())?;
+    }
+
+    Ok(())
+}
+
+/// Without `--steps`, each start node's walk covers its pre-period and one full cycle.
+pub fn run(map: &Map, start: &NodeMatcher, end: &NodeMatcher, args: &[String]) -> io::Result<()> {
+    let mut steps = None;
+    let mut filter = TraceFilter::default();
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = Some(args.next().and_then(|n| n.parse().ok()).expect("--steps needs a number")),
+            "--every" => filter.every = Some(args.next().and_then(|n| n.parse().ok()).filter(|&n| n > 0).expect("--every needs a positive number")),
+            "--hits-only" => filter.hits_only = true,
+            "--output" => output = Some(args.next().expect("--output needs a file name")),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let mut out: Box<dyn Write> = match output {
+        Some(file) => Box::new(BufWriter::new(File::create(file)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    for start_node in map.start_nodes(start) {
+        let steps = steps.unwrap_or_else(|| {
+            let ghost = GhostCycle::analyse(map, start_node, end);
+            ghost.pre_period + ghost.cycle_length
+        });
+        record(map, start_node, end, steps, &filter, &mut out)?;
+    }
+
+    out.flush()
+}
+
+pub fn load(file: &str) -> Result<Vec<Record>, String> {
+    let file = File::open(file).map_err(|e| format!("Could not open {}: {}", file, e))?;
+
+    BufReader::new(file)
+        .lines()
+        .map(|line| line.map_err(|e| e.to_string()).and_then(|line| Record::from_line(&line)))
+        .collect()
+}
+
+/// Prints where every walker stands at `step`: the last recorded step at or before it.
+fn show(walkers: &[(String, Vec<Record>)], step: usize) {
+    println!("Step {}", step);
+    for (walker, records) in walkers {
+        match records.partition_point(|record| record.step <= step) {
+            0 => println!("  {} has no recorded steps yet", walker),
+            latest => println!("  {}", records[latest - 1].to_line()),
+        }
+    }
+}
+
+/// Without `--seek`, reads commands from stdin: `n` or an empty line steps forward, `p` steps back,
+/// `s N` seeks to step N and `q` quits.
+pub fn replay(args: &[String]) -> Result<(), String> {
+    let file = args.first().ok_or("replay needs a trace file")?;
+    let records = load(file)?;
+    if records.is_empty() {
+        return Err(format!("{} contains no steps", file));
+    }
+
+    // Each walker's records are written in step order, so they can be searched by step.
+    let mut walkers: Vec<(String, Vec<Record>)> = Vec::new();
+    for record in records.iter() {
+        match walkers.iter_mut().find(|(walker, _)| walker == &record.walker) {
+            Some((_, walker_records)) => walker_records.push(record.clone()),
+            None => walkers.push((record.walker.to_string(), vec![record.clone()])),
+        }
+    }
+
+    if let Some(seek) = args.iter().position(|arg| arg == "--seek") {
+        let step = args.get(seek + 1).and_then(|n| n.parse().ok()).ok_or("--seek needs a number")?;
+        show(&walkers, step);
+        return Ok(());
+    }
+
+    // Only recorded steps are worth stopping at, so `n` and `p` move between those.
+    let mut steps: Vec<usize> = records.iter().map(|record| record.step).collect();
+    steps.sort();
+    steps.dedup();
+
+    let mut position = 0;
+    show(&walkers, steps[position]);
+
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let mut parts = line.split_ascii_whitespace();
+
+        match parts.next() {
+            None | Some("n") => position = (position + 1).min(steps.len() - 1),
+            Some("p") => position = position.saturating_sub(1),
+            Some("s") => {
+                let Some(step) = parts.next().and_then(|n| n.parse::<usize>().ok()) else {
+                    println!("Usage: s STEP");
+                    continue;
+                };
+                position = steps.partition_point(|&recorded| recorded <= step).saturating_sub(1);
+            },
+            Some("q") => break,
+            Some(command) => {
+                println!("Unknown command {}", command);
+                continue;
+            }
+        }
+
+        show(&walkers, steps[position]);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    fn example() -> Map {
+        parse_input("LR\n\n11A = (11B, XXX)\n11B = (XXX, 11Z)\n11Z = (11B, XXX)\nXXX = (XXX, XXX)").unwrap()
+    }
+
+    fn trace(steps: usize, filter: &TraceFilter) -> Vec<String> {
+        let map = example();
+        let mut out = Vec::new();
+        record(&map, map.ids["11A"], &NodeMatcher::Suffix("Z".to_string()), steps, filter, &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let record = Record {
+            walker: "11A".to_string(),
+            step: 3,
+            direction: Direction::Right,
+            from: "11B".to_string(),
+            to: "11Z".to_string(),
+            hit: true,
+        };
+        let line = record.to_line();
+        assert_eq!(line, "11A 3 R 11B 11Z hit");
+
+        let parsed = Record::from_line(&line).unwrap();
+        assert_eq!(parsed.walker, "11A");
+        assert_eq!(parsed.step, 3);
+        assert_eq!(parsed.direction, Direction::Right);
+        assert_eq!((parsed.from.as_str(), parsed.to.as_str()), ("11B", "11Z"));
+        assert!(parsed.hit);
+        assert_eq!(parsed.to_line(), line);
+
+        assert!(!Record::from_line("11A 0 L 11A 11B -").unwrap().hit);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(Record::from_line("11A 0 L 11A 11B").is_err());
+        assert!(Record::from_line("11A x L 11A 11B -").is_err());
+        assert!(Record::from_line("11A 0 U 11A 11B -").is_err());
+    }
+
+    #[test]
+    fn records_every_step() {
+        assert_eq!(trace(4, &TraceFilter::default()), [
+            "11A 0 L 11A 11B -",
+            "11A 1 R 11B 11Z hit",
+            "11A 2 L 11Z 11B -",
+            "11A 3 R 11B 11Z hit",
+        ]);
+    }
+
+    #[test]
+    fn filters_steps() {
+        assert_eq!(trace(4, &TraceFilter { every: Some(2), hits_only: false }), ["11A 0 L 11A 11B -", "11A 2 L 11Z 11B -"]);
+        assert_eq!(trace(4, &TraceFilter { every: None, hits_only: true }), ["11A 1 R 11B 11Z hit", "11A 3 R 11B 11Z hit"]);
+        assert!(trace(4, &TraceFilter { every: Some(2), hits_only: true }).is_empty());
+    }
+}
@@ -1,79 +1,189 @@
-fn parse_input(input: &str) -> Vec<Vec<i32>> {
+mod polynomial;
+mod rational;
+
+use polynomial::Number;
+use rational::Rational;
+
+/// How values are printed: exactly (integers, or fractions in rational mode) or as decimals.
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    Exact,
+    Decimal(usize),
+}
+
+impl Output {
+    fn show<T: Number>(&self, value: &T) -> String {
+        match self {
+            Output::Exact => value.to_string(),
+            Output::Decimal(places) => value.to_rational().to_decimal(*places),
+        }
+    }
+
+    fn show_all<T: Number>(&self, values: &[T]) -> String {
+        let values: Vec<String> = values.iter().map(|value| self.show(value)).collect();
+        format!("[{}]", values.join(", "))
+    }
+}
+
+fn parse_input<T: Number>(input: &str) -> Result<Vec<Vec<T>>, String> {
     input.lines()
-    .map(|line| 
+    .map(|line|
         line.split_ascii_whitespace()
-        .map(|n| n.parse::<i32>().unwrap() )
+        .map(T::parse)
         .collect()
     )
     .collect()
 }
 
-fn get_differences(input: Vec<i32>) -> Vec<i32> {
-    // get the differences between each number
-    let mut differences = Vec::new();
-    for i in 0..input.len() - 1 {
-        differences.push(input[i + 1] - input[i]);
+/// Sums `predict` over every history. If any history is flagged, the error lists all of them
+/// rather than only the first.
+fn sum_predictions<T: Number>(histories: &[Vec<T>], predict: fn(&[T]) -> Result<T, String>) -> Result<T, String> {
+    let mut sum = T::from_integer(0);
+    let mut flagged = Vec::new();
+
+    for (i, history) in histories.iter().enumerate() {
+        match predict(history) {
+            Ok(prediction) => sum = sum.checked_add(&prediction).ok_or("Sum of predictions overflows")?,
+            Err(e) => flagged.push(format!("History {}: {}", i + 1, e)),
+        }
+    }
+
+    if !flagged.is_empty() {
+        return Err(flagged.join("\n"));
     }
-    differences
+
+    Ok(sum)
 }
 
-fn get_layers(histories: Vec<Vec<i32>>) -> Vec<Vec<Vec<i32>>> {
-    return histories.iter().map(|history| {
-        let mut layers = Vec::new();
+fn part_one<T: Number>(input: &str, output: Output) {
+    let sum = parse_input::<T>(input).and_then(|histories| sum_predictions(&histories, polynomial::next_value));
 
-        let mut differences = get_differences(history.clone());
-        layers.push(differences.clone());
+    match sum {
+        Ok(sum) => println!("Part One: {}", output.show(&sum)),
+        Err(e) => println!("Part One: {}", e),
+    }
+}
 
-        loop {
-            if differences.iter().all(|n| n == &(0 as i32)) {
-                break;
-            }
+fn part_two<T: Number>(input: &str, output: Output) {
+    let sum = parse_input::<T>(input).and_then(|histories| sum_predictions(&histories, polynomial::previous_value));
 
-            differences = get_differences(differences.clone());
-            layers.push(differences.clone());
-        }
-        
-        layers
-    })
-    .collect();
+    match sum {
+        Ok(sum) => println!("Part Two: {}", output.show(&sum)),
+        Err(e) => println!("Part Two: {}", e),
+    }
 }
 
-fn part_one(input: &str) {
-    let histories = parse_input(input);
+fn number_arg<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    value.and_then(|n| n.parse().ok()).ok_or(format!("{} needs a number", flag))
+}
 
-    let growth_predictions: Vec<i32> = get_layers(histories.clone())
-    .iter()
-    .map(|history_layers| {
-        return history_layers.iter().fold(0, |acc, layer| acc + layer.last().unwrap());
-    })
-    .collect();
+/// Reports on every history in turn.
+fn extrapolate<T: Number>(input: &str, output: Output, args: &[String]) -> Result<(), String> {
+    let mut forward = 0;
+    let mut backward = 0;
+    let mut points = Vec::new();
+    let mut show_coefficients = false;
 
-    let sum = histories.iter().zip(growth_predictions)
-        .fold(0, |acc, (history, prediction)| acc + history.last().unwrap() + prediction);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--forward" => forward = number_arg(arg, args.next())?,
+            "--backward" => backward = number_arg(arg, args.next())?,
+            "--at" => points.push(number_arg(arg, args.next())?),
+            "--coefficients" => show_coefficients = true,
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
 
-    println!("Part One: {:?}", sum);
-    
-}
+    for (i, history) in parse_input::<T>(input)?.iter().enumerate() {
+        println!("History {}: {}", i + 1, output.show_all(history));
 
-fn part_two(input: &str) {
-    let histories = parse_input(input);
+        // Report a flagged history and carry on with the rest.
+        let report = || -> Result<(), String> {
+            if backward > 0 {
+                println!("  Backward: {}", output.show_all(&polynomial::extrapolate_backward(history, backward)?));
+            }
+            if forward > 0 {
+                println!("  Forward: {}", output.show_all(&polynomial::extrapolate_forward(history, forward)?));
+            }
+            for &x in points.iter() {
+                println!("  p({}) = {}", x, output.show(&polynomial::value_at(history, x)?));
+            }
+            if show_coefficients {
+                println!("  {}", polynomial::format_polynomial(&polynomial::coefficients(history)?));
+            }
+            Ok(())
+        };
 
-    let growth_predictions: Vec<i32> = get_layers(histories.clone())
-    .iter()
-    .map(|history_layers| {
-        return history_layers.iter().rev().fold(0, |acc, layer| return layer.first().unwrap() - acc);
-    })
-    .collect();
+        if let Err(e) = report() {
+            println!("  {}", e);
+        }
+    }
 
-    let sum = histories.iter().zip(growth_predictions)
-        .fold(0, |acc, (history, prediction)| acc + history.first().unwrap() - prediction);
+    Ok(())
+}
 
-    println!("Part Two: {:?}", sum);
+fn check<T: Number>(input: &str) -> Result<(), String> {
+    for (i, history) in parse_input::<T>(input)?.iter().enumerate() {
+        match polynomial::degree(history)? {
+            Some(degree) => println!("History {}: degree {}", i + 1, degree),
+            None if history.is_empty() => println!("History {}: empty", i + 1),
+            None if history.len() == 1 => println!("History {}: too short to confirm a degree", i + 1),
+            None => println!("History {}: not a polynomial, only degree {} fits", i + 1, history.len() - 1),
+        }
+    }
+
+    Ok(())
+}
+
+fn run<T: Number>(input: &str, output: Output, args: &[String]) -> Result<(), String> {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("extrapolate") => extrapolate::<T>(input, output, &args[1..]),
+        Some("check") => check::<T>(input),
+        _ => {
+            part_one::<T>(input, output);
+            part_two::<T>(input, output);
+            Ok(())
+        }
+    }
 }
 
+/// `--rational` reads decimals and fractions like `1.25` and `3/4` exactly instead of integers, and
+/// `--decimal N` prints results as decimals rounded to `N` places. Both go before any subcommand.
 fn main() {
     let input = include_str!("input.txt");
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut rational = false;
+    let mut output = Output::Exact;
 
-    part_one(input);
-    part_two(input);
+    loop {
+        match args.first().map(|arg| arg.as_str()) {
+            Some("--rational") => {
+                rational = true;
+                args.remove(0);
+            },
+            Some("--decimal") => {
+                match number_arg("--decimal", args.get(1)) {
+                    Ok(places) => output = Output::Decimal(places),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+                args.drain(..2);
+            },
+            _ => break,
+        }
+    }
+
+    let result = if rational {
+        run::<Rational>(input, output, &args)
+    } else {
+        run::<i128>(input, output, &args)
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }
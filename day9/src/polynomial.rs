@@ -0,0 +1,393 @@
+use std::fmt;
+
+use crate::rational::Rational;
+
+/// What the difference table needs from a reading. Plain integers are the fast path, rationals
+/// cover fractional readings exactly.
+pub trait Number: Copy + PartialEq + fmt::Display {
+    fn from_integer(value: i128) -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn to_rational(&self) -> Rational;
+    fn parse(text: &str) -> Result<Self, String>;
+}
+
+impl Number for i128 {
+    fn from_integer(value: i128) -> i128 {
+        value
+    }
+
+    fn checked_add(&self, other: &i128) -> Option<i128> {
+        i128::checked_add(*self, *other)
+    }
+
+    fn checked_sub(&self, other: &i128) -> Option<i128> {
+        i128::checked_sub(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i128) -> Option<i128> {
+        i128::checked_mul(*self, *other)
+    }
+
+    fn to_rational(&self) -> Rational {
+        Rational::integer(*self)
+    }
+
+    fn parse(text: &str) -> Result<i128, String> {
+        text.parse().map_err(|e| format!("Invalid reading {}: {}", text, e))
+    }
+}
+
+impl Number for Rational {
+    fn from_integer(value: i128) -> Rational {
+        Rational::integer(value)
+    }
+
+    fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        Rational::checked_add(self, other)
+    }
+
+    fn checked_sub(&self, other: &Rational) -> Option<Rational> {
+        Rational::checked_sub(self, other)
+    }
+
+    fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        Rational::checked_mul(self, other)
+    }
+
+    fn to_rational(&self) -> Rational {
+        *self
+    }
+
+    fn parse(text: &str) -> Result<Rational, String> {
+        Rational::parse(text)
+    }
+}
+
+/// The leading diagonal of the difference table, `Δ^i a_0` for every `i`, worked out straight from
+/// the history as `Δ^i a_0 = Σ (-1)^(i-k) C(i, k) a_k` so no other layer is ever built.
+fn leading_differences<T: Number>(history: &[T]) -> Result<Vec<T>, String> {
+    let mut differences = Vec::with_capacity(history.len());
+
+    for i in 0..history.len() {
+        let mut difference = T::from_integer(0);
+        let mut binomial: i128 = 1;
+
+        for (k, reading) in history.iter().enumerate().take(i + 1) {
+            if k > 0 {
+                // C(i, k) = C(i, k - 1) * (i - k + 1) / k, which is exact at every step.
+                binomial = binomial.checked_mul((i - k + 1) as i128).ok_or_else(overflow)? / k as i128;
+            }
+
+            let term = T::from_integer(binomial).checked_mul(reading).ok_or_else(overflow)?;
+            difference = if (i - k) % 2 == 0 {
+                difference.checked_add(&term)
+            } else {
+                difference.checked_sub(&term)
+            }
+            .ok_or_else(overflow)?;
+        }
+
+        differences.push(difference);
+    }
+
+    Ok(differences)
+}
+
+/// Evaluates the polynomial through `history` (taken at indices `0..len`) at index `x`. `x` may be
+/// negative or far outside the history.
+pub fn value_at<T: Number>(history: &[T], x: i64) -> Result<T, String> {
+    evaluate(&fitted_differences(history)?, x)
+}
+
+/// The lowest degree of polynomial the difference table shows fits `history`, found by taking
+/// differences until a layer of at least two entries is constant. `None` means no layer ever is:
+/// only the degree `len - 1` polynomial through every reading fits, and nothing in the data backs
+/// up a prediction made from it. A single reading never gets as far as a layer of two, so it is
+/// always `None`.
+pub fn degree<T: Number>(history: &[T]) -> Result<Option<usize>, String> {
+    let mut layer = history.to_vec();
+    let mut degree = 0;
+
+    while layer.len() >= 2 {
+        if layer.iter().all(|value| *value == layer[0]) {
+            return Ok(Some(degree));
+        }
+
+        for i in 0..layer.len() - 1 {
+            layer[i] = layer[i + 1].checked_sub(&layer[i]).ok_or_else(overflow)?;
+        }
+        layer.pop();
+        degree += 1;
+    }
+
+    Ok(None)
+}
+
+/// The leading differences up to the degree that fits, or an error flagging a history that is not
+/// a polynomial its difference table can confirm.
+fn fitted_differences<T: Number>(history: &[T]) -> Result<Vec<T>, String> {
+    if history.is_empty() {
+        return Err("Cannot extrapolate an empty history".to_string());
+    }
+
+    // No layer of one entry can show a degree, so a single reading backs up no prediction either.
+    if history.len() == 1 {
+        return Err("Too short to confirm a degree: a single reading fits a polynomial of any degree".to_string());
+    }
+
+    match degree(history)? {
+        Some(degree) => {
+            // A degree d polynomial is pinned down by its first d + 1 readings.
+            leading_differences(&history[..=degree])
+        },
+        None => Err(format!(
+            "Not a polynomial: no difference layer becomes constant, so only the degree {} polynomial through every reading fits",
+            history.len() - 1
+        )),
+    }
+}
+
+/// Newton's forward difference formula, `p(x) = Σ C(x, i) Δ^i a_0`.
+fn evaluate<T: Number>(differences: &[T], x: i64) -> Result<T, String> {
+    let x = x as i128;
+    let mut value = T::from_integer(0);
+    let mut binomial: i128 = 1;
+
+    for (i, difference) in differences.iter().enumerate() {
+        if i > 0 {
+            // C(x, i) = C(x, i - 1) * (x - i + 1) / i; the division is exact even for negative x.
+            binomial = binomial.checked_mul(x - i as i128 + 1).ok_or_else(overflow)? / i as i128;
+        }
+
+        let term = T::from_integer(binomial).checked_mul(difference).ok_or_else(overflow)?;
+        value = value.checked_add(&term).ok_or_else(overflow)?;
+    }
+
+    Ok(value)
+}
+
+/// The `count` values following the history, starting with the part one prediction.
+pub fn extrapolate_forward<T: Number>(history: &[T], count: usize) -> Result<Vec<T>, String> {
+    let differences = fitted_differences(history)?;
+    (0..count).map(|k| evaluate(&differences, (history.len() + k) as i64)).collect()
+}
+
+/// The `count` values preceding the history, nearest first, starting with the part two prediction.
+pub fn extrapolate_backward<T: Number>(history: &[T], count: usize) -> Result<Vec<T>, String> {
+    let differences = fitted_differences(history)?;
+    (1..=count).map(|k| evaluate(&differences, -(k as i64))).collect()
+}
+
+/// The coefficients of the polynomial through `history` in powers of the index, constant term
+/// first, with trailing zero coefficients dropped.
+pub fn coefficients<T: Number>(history: &[T]) -> Result<Vec<Rational>, String> {
+    let differences = fitted_differences(history)?;
+    let mut coefficients = vec![Rational::ZERO; differences.len()];
+
+    // `basis` holds the coefficients of C(x, i), grown one factor of (x - i + 1) / i at a time.
+    let mut basis = vec![Rational::ONE];
+
+    for (i, difference) in differences.iter().enumerate() {
+        if i > 0 {
+            let shift = Rational::integer(1 - i as i128);
+            let scale = Rational::integer(i as i128);
+            let mut next = vec![Rational::ZERO; basis.len() + 1];
+
+            for (power, coefficient) in basis.iter().enumerate() {
+                next[power + 1] = next[power + 1].checked_add(coefficient).ok_or_else(overflow)?;
+                let shifted = coefficient.checked_mul(&shift).ok_or_else(overflow)?;
+                next[power] = next[power].checked_add(&shifted).ok_or_else(overflow)?;
+            }
+
+            basis = next
+                .iter()
+                .map(|coefficient| coefficient.checked_div(&scale).ok_or_else(overflow))
+                .collect::<Result<_, _>>()?;
+        }
+
+        let difference = difference.to_rational();
+        for (power, coefficient) in basis.iter().enumerate() {
+            let term = coefficient.checked_mul(&difference).ok_or_else(overflow)?;
+            coefficients[power] = coefficients[power].checked_add(&term).ok_or_else(overflow)?;
+        }
+    }
+
+    while coefficients.len() > 1 && coefficients.last().is_some_and(|c| c.is_zero()) {
+        coefficients.pop();
+    }
+
+    Ok(coefficients)
+}
+
+/// Formats the coefficients as `p(x) = 1/2 x^2 - 3 x + 1`, highest power first.
+pub fn format_polynomial(coefficients: &[Rational]) -> String {
+    let mut terms = Vec::new();
+
+    for (power, coefficient) in coefficients.iter().enumerate().rev() {
+        if coefficient.is_zero() && coefficients.len() > 1 {
+            continue;
+        }
+
+        let negative = coefficient.numerator() < 0;
+        let magnitude = Rational::new(coefficient.numerator().abs(), coefficient.denominator()).unwrap();
+        let variable = match power {
+            0 => String::new(),
+            1 => "x".to_string(),
+            _ => format!("x^{}", power),
+        };
+
+        let term = if power == 0 {
+            magnitude.to_string()
+        } else if magnitude == Rational::ONE {
+            variable
+        } else {
+            format!("{} {}", magnitude, variable)
+        };
+
+        if terms.is_empty() {
+            terms.push(if negative { format!("-{}", term) } else { term });
+        } else {
+            terms.push(format!("{} {}", if negative { "-" } else { "+" }, term));
+        }
+    }
+
+    format!("p(x) = {}", terms.join(" "))
+}
+
+pub fn next_value<T: Number>(history: &[T]) -> Result<T, String> {
+    value_at(history, history.len() as i64)
+}
+
+pub fn previous_value<T: Number>(history: &[T]) -> Result<T, String> {
+    value_at(history, -1)
+}
+
+fn overflow() -> String {
+    "Arithmetic overflow while extrapolating".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [[i128; 6]; 3] = [
+        [0, 3, 6, 9, 12, 15],
+        [1, 3, 6, 10, 15, 21],
+        [10, 13, 16, 21, 30, 45],
+    ];
+
+    #[test]
+    fn leading_differences_of_the_example() {
+        assert_eq!(leading_differences(&EXAMPLE[0]), Ok(vec![0, 3, 0, 0, 0, 0]));
+        assert_eq!(leading_differences(&EXAMPLE[2]), Ok(vec![10, 3, 0, 2, 0, 0]));
+        assert_eq!(leading_differences::<i128>(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn predicts_the_example() {
+        let next: Vec<i128> = EXAMPLE.iter().map(|history| next_value(history).unwrap()).collect();
+        let previous: Vec<i128> = EXAMPLE.iter().map(|history| previous_value(history).unwrap()).collect();
+
+        assert_eq!(next, [18, 28, 68]);
+        assert_eq!(next.iter().sum::<i128>(), 114);
+        assert_eq!(previous, [-3, 0, 5]);
+        assert_eq!(previous.iter().sum::<i128>(), 2);
+    }
+
+    #[test]
+    fn evaluates_at_negative_and_distant_indices() {
+        assert_eq!(value_at(&EXAMPLE[2], 0), Ok(10));
+        assert_eq!(value_at(&EXAMPLE[2], -2), Ok(-4));
+        // p(x) = 1/3 x^3 - x^2 + 11/3 x + 10
+        assert_eq!(value_at(&EXAMPLE[2], 100), Ok(323_710));
+        assert_eq!(value_at::<i128>(&[], 0), Err("Cannot extrapolate an empty history".to_string()));
+    }
+
+    #[test]
+    fn reports_overflow() {
+        // 2^40 C(x, 2) has a constant second difference of 2^40, and C(i64::MAX, 2) times that does
+        // not fit an i128.
+        let h: i128 = 1 << 40;
+        assert_eq!(value_at(&[0, 0, h, 3 * h, 6 * h], i64::MAX), Err(overflow()));
+    }
+
+    #[test]
+    fn evaluates_the_leading_differences() {
+        let differences = [10, 3, 0, 2];
+
+        assert_eq!(evaluate(&differences, -1), Ok(5));
+        assert_eq!(evaluate(&differences, 100), Ok(323_710));
+        assert_eq!(evaluate(&[0, i128::MAX], 2), Err(overflow()));
+    }
+
+    #[test]
+    fn extrapolates_several_values() {
+        assert_eq!(extrapolate_forward(&EXAMPLE[2], 3), Ok(vec![68, 101, 146]));
+        assert_eq!(extrapolate_backward(&EXAMPLE[2], 3), Ok(vec![5, -4, -19]));
+        assert_eq!(extrapolate_forward(&EXAMPLE[2], 0), Ok(vec![]));
+    }
+
+    fn rationals(values: &[(i128, i128)]) -> Vec<Rational> {
+        values.iter().map(|&(numerator, denominator)| Rational::new(numerator, denominator).unwrap()).collect()
+    }
+
+    #[test]
+    fn coefficients_of_the_example() {
+        assert_eq!(coefficients(&EXAMPLE[0]), Ok(rationals(&[(0, 1), (3, 1)])));
+        assert_eq!(coefficients(&EXAMPLE[1]), Ok(rationals(&[(1, 1), (3, 2), (1, 2)])));
+        assert_eq!(coefficients(&EXAMPLE[2]), Ok(rationals(&[(10, 1), (11, 3), (-1, 1), (1, 3)])));
+        assert_eq!(coefficients(&[4, 4, 4]), Ok(rationals(&[(4, 1)])));
+    }
+
+    #[test]
+    fn formats_polynomials() {
+        assert_eq!(format_polynomial(&coefficients(&EXAMPLE[2]).unwrap()), "p(x) = 1/3 x^3 - x^2 + 11/3 x + 10");
+        assert_eq!(format_polynomial(&coefficients(&EXAMPLE[0]).unwrap()), "p(x) = 3 x");
+        assert_eq!(format_polynomial(&rationals(&[(-1, 2), (0, 1), (-1, 1)])), "p(x) = -x^2 - 1/2");
+        assert_eq!(format_polynomial(&[Rational::ZERO]), "p(x) = 0");
+    }
+
+    #[test]
+    fn flags_an_overflowing_history() {
+        // Alternating signs make Δ^i a_0 = ±2^i i64::MAX, which leaves the i128 range at i = 65.
+        let history: Vec<i128> = (0..66).map(|k| if k % 2 == 0 { i64::MAX as i128 } else { -(i64::MAX as i128) }).collect();
+
+        assert_eq!(next_value(&history), Err(overflow()));
+        assert_eq!(coefficients(&history), Err(overflow()));
+        assert_eq!(extrapolate_backward(&history, 2), Err(overflow()));
+    }
+
+    #[test]
+    fn finds_the_degree() {
+        assert_eq!(degree(&[5, 5, 5]), Ok(Some(0)));
+        assert_eq!(degree(&EXAMPLE[0]), Ok(Some(1)));
+        assert_eq!(degree(&EXAMPLE[2]), Ok(Some(3)));
+    }
+
+    #[test]
+    fn flags_histories_that_are_not_polynomials() {
+        let exponential = [1, 2, 4, 8, 16, 32];
+        assert_eq!(degree(&exponential), Ok(None));
+        assert!(next_value(&exponential).unwrap_err().starts_with("Not a polynomial"));
+
+        assert_eq!(degree(&[1, 2]), Ok(None));
+        assert!(next_value(&[1, 2]).unwrap_err().starts_with("Not a polynomial"));
+    }
+
+    #[test]
+    fn flags_a_single_reading_as_too_short() {
+        assert_eq!(degree(&[7]), Ok(None));
+        assert!(next_value(&[7]).unwrap_err().starts_with("Too short to confirm a degree"));
+        assert!(coefficients(&[7]).unwrap_err().starts_with("Too short to confirm a degree"));
+    }
+
+    #[test]
+    fn predicts_rational_histories() {
+        let history: Vec<Rational> = ["1/2", "1", "1.5"].iter().map(|text| Rational::parse(text).unwrap()).collect();
+
+        assert_eq!(next_value(&history), Ok(Rational::integer(2)));
+        assert_eq!(previous_value(&history), Ok(Rational::ZERO));
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+/// An exact fraction, always kept in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        return a.abs();
+    }
+
+    gcd(b, a % b)
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { numerator: 0, denominator: 1 };
+    pub const ONE: Rational = Rational { numerator: 1, denominator: 1 };
+
+    /// Builds `numerator / denominator` in lowest terms, or `None` for a zero denominator.
+    pub fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numerator, denominator);
+        let sign = if denominator < 0 { -1 } else { 1 };
+
+        Some(Rational {
+            numerator: (numerator / divisor).checked_mul(sign)?,
+            denominator: (denominator / divisor).checked_mul(sign)?,
+        })
+    }
+
+    pub fn integer(value: i128) -> Rational {
+        Rational { numerator: value, denominator: 1 }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        // Scale by the smaller lcm rather than the product of the denominators to delay overflow.
+        let divisor = gcd(self.denominator, other.denominator);
+        let left = self.numerator.checked_mul(other.denominator / divisor)?;
+        let right = other.numerator.checked_mul(self.denominator / divisor)?;
+        Rational::new(left.checked_add(right)?, (self.denominator / divisor).checked_mul(other.denominator)?)
+    }
+
+    pub fn checked_sub(&self, other: &Rational) -> Option<Rational> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        // Cancel across before multiplying so the intermediate products stay small.
+        let first = gcd(self.numerator, other.denominator).max(1);
+        let second = gcd(other.numerator, self.denominator).max(1);
+        Rational::new(
+            (self.numerator / first).checked_mul(other.numerator / second)?,
+            (self.denominator / second).checked_mul(other.denominator / first)?,
+        )
+    }
+
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        if other.is_zero() {
+            return None;
+        }
+
+        self.checked_mul(&Rational::new(other.denominator, other.numerator)?)
+    }
+
+    pub fn checked_neg(&self) -> Option<Rational> {
+        Some(Rational { numerator: self.numerator.checked_neg()?, denominator: self.denominator })
+    }
+
+    /// Parses an integer (`7`), a fraction (`-3/4`) or a decimal (`1.25`) exactly.
+    pub fn parse(text: &str) -> Result<Rational, String> {
+        let invalid = || format!("Invalid number {}", text);
+
+        if let Some((numerator, denominator)) = text.split_once('/') {
+            let numerator = numerator.parse::<i128>().map_err(|_| invalid())?;
+            let denominator = denominator.parse::<i128>().map_err(|_| invalid())?;
+            return Rational::new(numerator, denominator).ok_or_else(invalid);
+        }
+
+        if let Some((whole, fraction)) = text.split_once('.') {
+            if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+
+            // At most one sign, and only digits after it.
+            let (negative, whole) = match whole.strip_prefix('-') {
+                Some(whole) => (true, whole),
+                None => (false, whole.strip_prefix('+').unwrap_or(whole)),
+            };
+            if !whole.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+
+            let digits = format!("{}{}", whole, fraction);
+            let magnitude = digits.parse::<i128>().map_err(|_| invalid())?;
+            let scale = 10i128.checked_pow(fraction.len() as u32).ok_or_else(invalid)?;
+            return Rational::new(if negative { -magnitude } else { magnitude }, scale).ok_or_else(invalid);
+        }
+
+        text.parse::<i128>().map(Rational::integer).map_err(|_| invalid())
+    }
+
+    /// Writes the value as a decimal rounded half away from zero to `places` digits, falling back
+    /// to the exact fraction if scaling it up would overflow.
+    pub fn to_decimal(self, places: usize) -> String {
+        let Some(scaled) = 10i128
+            .checked_pow(places as u32)
+            .and_then(|scale| self.numerator.checked_mul(scale))
+        else {
+            return self.to_string();
+        };
+
+        let mut rounded = scaled / self.denominator;
+        let remainder = scaled % self.denominator;
+        if remainder.unsigned_abs() * 2 >= self.denominator.unsigned_abs() {
+            rounded += scaled.signum();
+        }
+
+        let sign = if rounded < 0 { "-" } else { "" };
+        let digits = format!("{:0>width$}", rounded.unsigned_abs(), width = places + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - places);
+
+        if places == 0 {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, fraction)
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational(numerator: i128, denominator: i128) -> Rational {
+        Rational::new(numerator, denominator).unwrap()
+    }
+
+    #[test]
+    fn new_reduces_to_lowest_terms() {
+        assert_eq!(rational(6, 8), rational(3, 4));
+        assert_eq!((rational(6, -8).numerator(), rational(6, -8).denominator()), (-3, 4));
+        assert_eq!((rational(-6, -8).numerator(), rational(-6, -8).denominator()), (3, 4));
+        assert_eq!(rational(0, -5), Rational::ZERO);
+        assert_eq!(Rational::new(1, 0), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(rational(1, 2).checked_add(&rational(1, 3)), Some(rational(5, 6)));
+        assert_eq!(rational(1, 6).checked_add(&rational(-1, 6)), Some(Rational::ZERO));
+        assert_eq!(rational(1, 2).checked_sub(&rational(3, 4)), Some(rational(-1, 4)));
+        assert_eq!(rational(2, 3).checked_mul(&rational(9, 4)), Some(rational(3, 2)));
+        assert_eq!(rational(-2, 3).checked_mul(&Rational::ZERO), Some(Rational::ZERO));
+        assert_eq!(rational(2, 3).checked_div(&rational(4, 9)), Some(rational(3, 2)));
+        assert_eq!(rational(2, 3).checked_div(&Rational::ZERO), None);
+    }
+
+    #[test]
+    fn arithmetic_overflow() {
+        let huge = Rational::integer(i128::MAX);
+        assert_eq!(huge.checked_add(&Rational::ONE), None);
+        assert_eq!(huge.checked_mul(&Rational::integer(2)), None);
+        assert_eq!(Rational::integer(i128::MIN).checked_neg(), None);
+    }
+
+    #[test]
+    fn parses_integers_fractions_and_decimals() {
+        assert_eq!(Rational::parse("7"), Ok(Rational::integer(7)));
+        assert_eq!(Rational::parse("-3/4"), Ok(rational(-3, 4)));
+        assert_eq!(Rational::parse("6/8"), Ok(rational(3, 4)));
+        assert_eq!(Rational::parse("1.25"), Ok(rational(5, 4)));
+        assert_eq!(Rational::parse("-1.5"), Ok(rational(-3, 2)));
+        assert_eq!(Rational::parse("+1.5"), Ok(rational(3, 2)));
+        assert_eq!(Rational::parse("-0.05"), Ok(rational(-1, 20)));
+        assert_eq!(Rational::parse(".5"), Ok(rational(1, 2)));
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for text in ["--1.5", "+-1.5", "-+1.5", "1.", "1.-5", "1-2.5", "1/0", "a", "1.5.5", "--3/4"] {
+            assert_eq!(Rational::parse(text), Err(format!("Invalid number {}", text)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rounds_decimals_half_away_from_zero() {
+        assert_eq!(rational(1, 3).to_decimal(3), "0.333");
+        assert_eq!(rational(2, 3).to_decimal(3), "0.667");
+        assert_eq!(rational(1, 20).to_decimal(1), "0.1");
+        assert_eq!(rational(-1, 20).to_decimal(1), "-0.1");
+        assert_eq!(rational(-1, 40).to_decimal(1), "0.0");
+        assert_eq!(rational(-5, 2).to_decimal(0), "-3");
+        assert_eq!(Rational::integer(7).to_decimal(2), "7.00");
+    }
+
+    #[test]
+    fn falls_back_to_the_fraction_on_overflow() {
+        assert_eq!(rational(i128::MAX, 7).to_decimal(2), format!("{}/7", i128::MAX));
+    }
+}